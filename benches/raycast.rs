@@ -0,0 +1,31 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rustgame3::{raycast, raycast_into, Grid};
+use std::collections::HashSet;
+
+/// Seeded 200x200 map with scattered walls, observer near the middle
+fn cave() -> (Grid, i32, i32) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut grid = Grid::new(200, 200);
+    for cell in grid.cells.iter_mut() {
+        if rng.gen_bool(0.08) {
+            *cell = 1;
+        }
+    }
+    grid.set_cell(100, 100, 0);
+    (grid, 100, 100)
+}
+
+fn bench_raycast(c: &mut Criterion) {
+    let (grid, x, y) = cave();
+    c.bench_function("raycast_cave_200", |b| b.iter(|| raycast(black_box(&grid), x, y)));
+
+    let open = Grid::new(200, 200);
+    let mut visible = HashSet::new();
+    c.bench_function("raycast_into_open_200", |b| {
+        b.iter(|| raycast_into(black_box(&open), 100, 100, &mut visible))
+    });
+}
+
+criterion_group!(benches, bench_raycast);
+criterion_main!(benches);
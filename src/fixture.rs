@@ -0,0 +1,46 @@
+use crate::grid::Grid;
+use crate::raycast::raycast;
+use serde::{Deserialize, Serialize};
+
+/// Test data structure matching C# JSON export format
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RaycastTestData {
+    #[serde(rename = "testName")]
+    pub test_name: String,
+    #[serde(rename = "gridRows")]
+    pub grid_rows: i32,
+    #[serde(rename = "gridCols")]
+    pub grid_cols: i32,
+    #[serde(rename = "blockedCells")]
+    pub blocked_cells: Vec<i32>,
+    #[serde(rename = "startX")]
+    pub start_x: i32,
+    #[serde(rename = "startY")]
+    pub start_y: i32,
+    #[serde(rename = "expectedVisible")]
+    pub expected_visible: Vec<i32>,
+    pub log: Vec<String>,
+}
+
+impl RaycastTestData {
+    /// Capture a grid and observer as a regression fixture
+    /// The expected set is the current Rust raycast result; the log is left empty
+    pub fn capture(test_name: &str, grid: &Grid, start_x: i32, start_y: i32) -> Self {
+        let blocked_cells = (0..grid.rows * grid.cols)
+            .filter(|&id| grid.is_blocked_id(id))
+            .collect();
+        let mut expected_visible: Vec<i32> = raycast(grid, start_x, start_y).into_iter().collect();
+        expected_visible.sort_unstable();
+
+        RaycastTestData {
+            test_name: test_name.to_string(),
+            grid_rows: grid.rows,
+            grid_cols: grid.cols,
+            blocked_cells,
+            start_x,
+            start_y,
+            expected_visible,
+            log: Vec::new(),
+        }
+    }
+}
@@ -1,3 +1,124 @@
+use crate::line::CellLine;
+use rand::Rng;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+
+/// Cardinal direction of a cell edge (positive Y is downward, so North is y-1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Unit (dx, dy) offset of the neighbor across this edge
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+
+    /// The same edge seen from the neighboring cell
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+
+    /// Direction after rotating the grid 90 degrees clockwise
+    pub fn rotated_cw(self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    /// Direction after mirroring the grid
+    pub fn mirrored(self, axis: MirrorAxis) -> Direction {
+        match (axis, self) {
+            (MirrorAxis::Horizontal, Direction::East | Direction::West)
+            | (MirrorAxis::Vertical, Direction::North | Direction::South) => self.opposite(),
+            _ => self,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        match self {
+            Direction::North => 1,
+            Direction::East => 2,
+            Direction::South => 4,
+            Direction::West => 8,
+        }
+    }
+}
+
+/// How `Grid::mirrored` flips the layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    /// Left-right flip (x becomes cols - 1 - x)
+    Horizontal,
+    /// Top-bottom flip (y becomes rows - 1 - y)
+    Vertical,
+}
+
+/// Cell ID newtype so IDs can't be mixed up with x/y coordinates
+///
+/// ```compile_fail
+/// use rustgame3::Grid;
+/// let grid = Grid::new(4, 4);
+/// let (x, _y) = grid.coords(grid.id(1, 2));
+/// grid.coords(x); // a coordinate is not a cell ID
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CellId(pub i32);
+
+/// How `Grid::overlay` combines a stamp with the cells underneath
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayMode {
+    /// Copy stamp cells as they are (free cells carve, walls fill)
+    Replace,
+    /// Add the stamp's walls, keep everything else
+    Or,
+    /// Carve: the stamp's walls become free cells
+    AndNot,
+}
+
+/// Maximal horizontal run of free cells in one row (the C# `Line`)
+/// `start_x` and `end_x` are inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineSegment {
+    pub y: i32,
+    pub start_x: i32,
+    pub end_x: i32,
+}
+
+impl LineSegment {
+    /// True if column `x` lies on the segment
+    pub fn contains(&self, x: i32) -> bool {
+        x >= self.start_x && x <= self.end_x
+    }
+
+    /// Number of cells on the segment
+    pub fn width(&self) -> i32 {
+        self.end_x - self.start_x + 1
+    }
+}
+
+/// Side length (in cells) of the square chunks used for dirty tracking
+pub const CHUNK_SIZE: i32 = 8;
+
 /// Grid structure for storing cell states
 /// Cell values: 0=free/walkable, 1=blocked/wall, -1=chasm (unused for size=1 observer)
 #[derive(Clone)]
@@ -5,6 +126,17 @@ pub struct Grid {
     pub rows: i32,
     pub cols: i32,
     pub cells: Vec<i32>,
+    /// Thin walls between cells: one bit per `Direction` for each cell
+    pub edges: Vec<u8>,
+    /// Incremented on every change made through `set_cell`/`set_edge`
+    /// Derived data can compare it to detect a stale cache
+    pub revision: u64,
+    /// Per-chunk dirty flags (row-major over `CHUNK_SIZE` chunks)
+    dirty: Vec<bool>,
+    /// Lazily computed region label per cell (-1 = blocked), reset by `bump_revision`
+    region_cache: OnceLock<Vec<i32>>,
+    /// Lazily computed `distance_transform`, reset like `region_cache`
+    distance_cache: OnceLock<Vec<f32>>,
 }
 
 impl Grid {
@@ -14,9 +146,46 @@ impl Grid {
             rows,
             cols,
             cells: vec![0; (rows * cols) as usize],
+            edges: vec![0; (rows * cols) as usize],
+            revision: 0,
+            // Everything needs processing once
+            dirty: vec![true; (Self::chunk_count(rows) * Self::chunk_count(cols)) as usize],
+            region_cache: OnceLock::new(),
+            distance_cache: OnceLock::new(),
         }
     }
 
+    /// Record a tracked edit: new revision, derived caches rebuilt on next query
+    fn bump_revision(&mut self) {
+        self.revision += 1;
+        self.region_cache.take();
+        self.distance_cache.take();
+    }
+
+    fn chunk_count(cells: i32) -> i32 {
+        (cells + CHUNK_SIZE - 1) / CHUNK_SIZE
+    }
+
+    fn mark_dirty(&mut self, x: i32, y: i32) {
+        let chunk = (x / CHUNK_SIZE) + (y / CHUNK_SIZE) * Self::chunk_count(self.cols);
+        self.dirty[chunk as usize] = true;
+    }
+
+    /// Chunks (chunk_x, chunk_y) changed since the last `clear_dirty`
+    /// Chunk (cx, cy) covers cells x in cx*CHUNK_SIZE..(cx+1)*CHUNK_SIZE, likewise for y
+    pub fn dirty_chunks(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let chunk_cols = Self::chunk_count(self.cols);
+        self.dirty.iter()
+            .enumerate()
+            .filter(|(_, &dirty)| dirty)
+            .map(move |(i, _)| (i as i32 % chunk_cols, i as i32 / chunk_cols))
+    }
+
+    /// Reset all dirty flags (call after the changed chunks have been consumed)
+    pub fn clear_dirty(&mut self) {
+        self.dirty.iter_mut().for_each(|d| *d = false);
+    }
+
     /// Create a grid with specific blocked cells
     pub fn with_blocked(rows: i32, cols: i32, blocked: &[i32]) -> Self {
         let mut grid = Self::new(rows, cols);
@@ -44,12 +213,26 @@ impl Grid {
         self.cells[id as usize] == 1
     }
 
+    /// Convert (x, y) coordinates to a typed cell ID
+    pub fn id(&self, x: i32, y: i32) -> CellId {
+        debug_assert!(x >= 0 && x < self.cols && y >= 0 && y < self.rows, "({}, {}) out of bounds", x, y);
+        CellId(self.get_id(x, y))
+    }
+
+    /// Convert a typed cell ID to (x, y) coordinates
+    pub fn coords(&self, id: CellId) -> (i32, i32) {
+        debug_assert!(id.0 >= 0 && id.0 < self.rows * self.cols, "{:?} out of bounds", id);
+        self.get_coords(id.0)
+    }
+
     /// Convert (x, y) coordinates to cell ID
+    /// Raw `i32` form used by the raycaster and test data; prefer `id` in new code
     pub fn get_id(&self, x: i32, y: i32) -> i32 {
         x + y * self.cols
     }
 
     /// Convert cell ID to (x, y) coordinates
+    /// Raw `i32` form used by the raycaster and test data; prefer `coords` in new code
     pub fn get_coords(&self, id: i32) -> (i32, i32) {
         (id % self.cols, id / self.cols)
     }
@@ -66,7 +249,895 @@ impl Grid {
     pub fn set_cell(&mut self, x: i32, y: i32, value: i32) {
         if x >= 0 && x < self.cols && y >= 0 && y < self.rows {
             let id = self.get_id(x, y);
-            self.cells[id as usize] = value;
+            if self.cells[id as usize] != value {
+                self.cells[id as usize] = value;
+                self.bump_revision();
+                self.mark_dirty(x, y);
+            }
+        }
+    }
+
+    /// Apply several `(x, y, value)` cell changes with a single `revision` bump
+    /// Out-of-bounds and no-op changes are skipped; returns the IDs that actually changed
+    pub fn set_cells(&mut self, changes: &[(i32, i32, i32)]) -> Vec<i32> {
+        let mut changed = Vec::new();
+        for &(x, y, value) in changes {
+            if x >= 0 && x < self.cols && y >= 0 && y < self.rows {
+                let id = self.get_id(x, y);
+                if self.cells[id as usize] != value {
+                    self.cells[id as usize] = value;
+                    self.mark_dirty(x, y);
+                    changed.push(id);
+                }
+            }
+        }
+        if !changed.is_empty() {
+            self.bump_revision();
+        }
+        changed
+    }
+
+    /// Change the grid dimensions, keeping cells and edges in the overlapping area
+    /// Newly exposed cells are free; negative dimensions are treated as 0
+    pub fn resize(&mut self, rows: i32, cols: i32) {
+        let (rows, cols) = (rows.max(0), cols.max(0));
+        let mut resized = Self::new(rows, cols);
+        for y in 0..rows.min(self.rows) {
+            for x in 0..cols.min(self.cols) {
+                let old_id = self.get_id(x, y) as usize;
+                let new_id = resized.get_id(x, y) as usize;
+                resized.cells[new_id] = self.cells[old_id];
+                resized.edges[new_id] = self.edges[old_id];
+            }
+        }
+        resized.revision = self.revision + 1;
+        *self = resized;
+    }
+
+    /// Clamp (x, y) to the nearest in-bounds cell, or `None` if the grid has no cells
+    /// Use after a resize to pull observers back onto the grid
+    pub fn clamp_position(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        if self.rows <= 0 || self.cols <= 0 {
+            return None;
+        }
+        Some((x.clamp(0, self.cols - 1), y.clamp(0, self.rows - 1)))
+    }
+
+    /// All free in-bounds cells as (x, y), in row-major order
+    pub fn walkable_cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..self.rows * self.cols)
+            .filter(|&id| !self.is_blocked_id(id))
+            .map(|id| self.get_coords(id))
+    }
+
+    /// Uniformly random free cell, or `None` if every cell is blocked
+    /// Pass a seeded RNG for reproducible placement
+    pub fn random_walkable<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(i32, i32)> {
+        let count = self.walkable_cells().count();
+        if count == 0 {
+            return None;
+        }
+        self.walkable_cells().nth(rng.gen_range(0..count))
+    }
+
+    /// Cell IDs of the orthogonally connected free region containing (x, y)
+    /// Blocked edges separate regions; a blocked start cell yields an empty set
+    pub fn flood_fill_region(&self, x: i32, y: i32) -> HashSet<i32> {
+        let mut region = HashSet::new();
+        if self.is_blocked(x, y) {
+            return region;
+        }
+
+        let mut stack = vec![(x, y)];
+        region.insert(self.get_id(x, y));
+        while let Some((cx, cy)) = stack.pop() {
+            for dir in [Direction::North, Direction::East, Direction::South, Direction::West] {
+                let (dx, dy) = dir.offset();
+                let (nx, ny) = (cx + dx, cy + dy);
+                if self.can_step(cx, cy, dir) && region.insert(self.get_id(nx, ny)) {
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        region
+    }
+
+    /// Connected-region label of (x, y), or `None` for blocked/out-of-bounds cells
+    /// Labels are cached and rebuilt on the first query after a tracked edit
+    /// (edits made by writing `cells` directly are not tracked)
+    pub fn region_of(&self, x: i32, y: i32) -> Option<i32> {
+        if self.is_blocked(x, y) {
+            return None;
+        }
+
+        let labels = self.region_cache.get_or_init(|| self.compute_region_labels());
+        Some(labels[self.get_id(x, y) as usize]).filter(|&label| label >= 0)
+    }
+
+    /// True if both cells are free and connected
+    pub fn same_region(&self, a: (i32, i32), b: (i32, i32)) -> bool {
+        match (self.region_of(a.0, a.1), self.region_of(b.0, b.1)) {
+            (Some(region_a), Some(region_b)) => region_a == region_b,
+            _ => false,
+        }
+    }
+
+    fn compute_region_labels(&self) -> Vec<i32> {
+        let mut labels = vec![-1; self.cells.len()];
+        let mut next_label = 0;
+        for (x, y) in self.walkable_cells() {
+            if labels[self.get_id(x, y) as usize] < 0 {
+                for id in self.flood_fill_region(x, y) {
+                    labels[id as usize] = next_label;
+                }
+                next_label += 1;
+            }
+        }
+        labels
+    }
+
+    /// Narrow passages (doors, chokepoints) connecting otherwise separate areas
+    ///
+    /// Candidates are wall-bounded straight runs of free cells (horizontal runs for gaps
+    /// in horizontal walls, vertical runs for gaps in vertical walls); blocked edges
+    /// bound runs like walls do. A run is a gap if it is a pinch (the passage is wider
+    /// right before it and widens again after it, possibly past a corridor of the same
+    /// width) and removing it splits its region.
+    /// A corridor is reported once, at its entrance.
+    /// Returns the middle cell of each gap and its width, sorted by position.
+    pub fn find_gaps(&self) -> Vec<((i32, i32), i32)> {
+        let mut gaps = Vec::new();
+
+        for (axis, side) in [((1, 0), (0, 1)), ((0, 1), (1, 0))] {
+            // Removing a run splits its region exactly when the run is a cut vertex
+            // of the graph of runs, so one linear pass finds every splitting run
+            let (runs, adjacency) = self.run_graph(axis, side);
+            let splits = articulation_points(&adjacency);
+
+            for (&(x, y), _) in runs.iter().zip(&splits).filter(|&(_, &split)| split) {
+                let width = self.free_run_width(x, y, axis);
+                let run: Vec<(i32, i32)> = (0..width).map(|k| (x + k * axis.0, y + k * axis.1)).collect();
+
+                let widest_at = |offset: i32| {
+                    run.iter()
+                        .map(|&(rx, ry)| self.free_run_width(rx + offset * side.0, ry + offset * side.1, axis))
+                        .max()
+                        .unwrap_or(0)
+                };
+                // Walk through a same-width corridor until the passage widens or narrows
+                let widens_after = || {
+                    let mut offset = 1;
+                    loop {
+                        let w = widest_at(offset);
+                        if w != width {
+                            return w > width;
+                        }
+                        offset += 1;
+                    }
+                };
+                if widest_at(-1) > width && widens_after() {
+                    gaps.push((run[((width - 1) / 2) as usize], width));
+                }
+            }
+        }
+
+        gaps.sort();
+        gaps.dedup();
+        gaps
+    }
+
+    /// Maximal runs of free cells along `axis` (first cell of each) and which runs
+    /// touch across `side`, using the same stepping rules as `flood_fill_region`
+    fn run_graph(&self, axis: (i32, i32), side: (i32, i32)) -> (Vec<(i32, i32)>, Vec<Vec<usize>>) {
+        let (backward, forward) = axis_directions(axis);
+        let (_, across) = axis_directions(side);
+
+        let mut run_of = vec![usize::MAX; self.cells.len()];
+        let mut runs = Vec::new();
+        for (x, y) in self.walkable_cells() {
+            if self.can_step(x, y, backward) {
+                continue;
+            }
+            let (mut cx, mut cy) = (x, y);
+            run_of[self.get_id(cx, cy) as usize] = runs.len();
+            while self.can_step(cx, cy, forward) {
+                (cx, cy) = (cx + axis.0, cy + axis.1);
+                run_of[self.get_id(cx, cy) as usize] = runs.len();
+            }
+            runs.push((x, y));
+        }
+
+        let mut adjacency = vec![Vec::new(); runs.len()];
+        for (x, y) in self.walkable_cells() {
+            if self.can_step(x, y, across) {
+                let a = run_of[self.get_id(x, y) as usize];
+                let b = run_of[self.get_id(x + side.0, y + side.1) as usize];
+                adjacency[a].push(b);
+                adjacency[b].push(a);
+            }
+        }
+        for neighbors in &mut adjacency {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+        (runs, adjacency)
+    }
+
+    /// The free segment of row `y` containing (x, y), bounded by walls, blocked
+    /// East/West edges or the grid edge (the `can_step` rules)
+    /// Returns `None` for blocked or out-of-bounds cells
+    pub fn line_segment_at(&self, x: i32, y: i32) -> Option<LineSegment> {
+        if self.is_blocked(x, y) {
+            return None;
+        }
+
+        let mut start_x = x;
+        while self.can_step(start_x, y, Direction::West) {
+            start_x -= 1;
+        }
+        let mut end_x = x;
+        while self.can_step(end_x, y, Direction::East) {
+            end_x += 1;
+        }
+        Some(LineSegment { y, start_x, end_x })
+    }
+
+    /// Narrowest free-space width perpendicular to travel along a waypoint path
+    /// Mostly-horizontal segments are measured vertically and vice versa.
+    /// Returns 0 for paths with fewer than two waypoints or that cross a blocked cell.
+    pub fn min_corridor_width_along(&self, path: &[(i32, i32)]) -> i32 {
+        let mut min_width: Option<i32> = None;
+
+        for segment in path.windows(2) {
+            let (x0, y0) = segment[0];
+            let (x1, y1) = segment[1];
+            let (dx, dy) = (x1 - x0, y1 - y0);
+            let steps = dx.abs().max(dy.abs()).max(1);
+            // Travelling mostly along X -> measure the free run along Y
+            let perpendicular = if dx.abs() >= dy.abs() { (0, 1) } else { (1, 0) };
+
+            for i in 0..=steps {
+                let x = x0 + dx * i / steps;
+                let y = y0 + dy * i / steps;
+                let width = self.free_run_width(x, y, perpendicular);
+                min_width = Some(min_width.map_or(width, |w| w.min(width)));
+            }
+        }
+
+        min_width.unwrap_or(0)
+    }
+
+    /// Length of the contiguous free run through (x, y) along `axis`
+    /// The run ends at blocked cells and blocked edges (the `can_step` rules)
+    fn free_run_width(&self, x: i32, y: i32, axis: (i32, i32)) -> i32 {
+        if self.is_blocked(x, y) {
+            return 0;
+        }
+
+        let (backward, forward) = axis_directions(axis);
+        let mut width = 1;
+        for dir in [backward, forward] {
+            let (dx, dy) = dir.offset();
+            let (mut cx, mut cy) = (x, y);
+            while self.can_step(cx, cy, dir) {
+                (cx, cy) = (cx + dx, cy + dy);
+                width += 1;
+            }
+        }
+        width
+    }
+
+    /// Set every in-bounds cell on the line from (x0, y0) to (x1, y1) to `value`
+    /// Returns the IDs of the cells that actually changed
+    pub fn paint_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, value: i32) -> Vec<i32> {
+        let changes: Vec<(i32, i32, i32)> = CellLine::new(x0, y0, x1, y1).map(|(x, y)| (x, y, value)).collect();
+        self.set_cells(&changes)
+    }
+
+    /// Composite `stamp` onto this grid with its top-left cell at (at_x, at_y)
+    /// Parts of the stamp outside the grid are clipped.
+    pub fn overlay(&mut self, stamp: &Grid, at_x: i32, at_y: i32, mode: OverlayMode) {
+        let mut changes = Vec::new();
+        for sy in 0..stamp.rows {
+            for sx in 0..stamp.cols {
+                let (x, y) = (at_x + sx, at_y + sy);
+                if x < 0 || x >= self.cols || y < 0 || y >= self.rows {
+                    continue;
+                }
+                let stamp_value = stamp.get_cell(sx, sy);
+                match mode {
+                    OverlayMode::Replace => changes.push((x, y, stamp_value)),
+                    OverlayMode::Or if stamp_value == 1 => changes.push((x, y, 1)),
+                    OverlayMode::AndNot if stamp_value == 1 => changes.push((x, y, 0)),
+                    _ => {}
+                }
+            }
+        }
+        self.set_cells(&changes);
+    }
+
+    /// Copy of the grid with walls grown by `radius_cells` (configuration space)
+    /// Every free cell within `radius_cells` (Chebyshev distance) of a blocked or
+    /// out-of-bounds cell becomes blocked, so a point path on the result keeps a
+    /// body of that radius clear of walls.
+    pub fn inflated(&self, radius_cells: i32) -> Grid {
+        let mut inflated = self.clone();
+        if radius_cells <= 0 {
+            return inflated;
+        }
+
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                if self.is_blocked(x, y) {
+                    continue;
+                }
+                let near_wall = (-radius_cells..=radius_cells).any(|dy| {
+                    (-radius_cells..=radius_cells).any(|dx| self.is_blocked(x + dx, y + dy))
+                });
+                if near_wall {
+                    inflated.set_cell(x, y, 1);
+                }
+            }
+        }
+
+        inflated
+    }
+
+    /// Copy of the grid rotated 90 degrees clockwise (rows and cols swap)
+    /// Cells and edges move together; use `rotated_90_coords` to remap positions
+    pub fn rotated_90(&self) -> Grid {
+        self.transformed(self.cols, self.rows, |x, y| self.rotated_90_coords(x, y), Direction::rotated_cw)
+    }
+
+    /// Where (x, y) ends up in `rotated_90()`
+    pub fn rotated_90_coords(&self, x: i32, y: i32) -> (i32, i32) {
+        (self.rows - 1 - y, x)
+    }
+
+    /// Mirrored copy of the grid (same dimensions)
+    pub fn mirrored(&self, axis: MirrorAxis) -> Grid {
+        self.transformed(self.rows, self.cols, |x, y| self.mirrored_coords(axis, x, y), |d| d.mirrored(axis))
+    }
+
+    /// Where (x, y) ends up in `mirrored(axis)`
+    pub fn mirrored_coords(&self, axis: MirrorAxis, x: i32, y: i32) -> (i32, i32) {
+        match axis {
+            MirrorAxis::Horizontal => (self.cols - 1 - x, y),
+            MirrorAxis::Vertical => (x, self.rows - 1 - y),
+        }
+    }
+
+    fn transformed(
+        &self,
+        rows: i32,
+        cols: i32,
+        map_coords: impl Fn(i32, i32) -> (i32, i32),
+        map_dir: impl Fn(Direction) -> Direction,
+    ) -> Grid {
+        let mut result = Grid::new(rows, cols);
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                let (nx, ny) = map_coords(x, y);
+                let (from, to) = (self.get_id(x, y) as usize, result.get_id(nx, ny) as usize);
+                result.cells[to] = self.cells[from];
+                for dir in [Direction::North, Direction::East, Direction::South, Direction::West] {
+                    if self.edges[from] & dir.bit() != 0 {
+                        result.edges[to] |= map_dir(dir).bit();
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Distance from each cell to the nearest blocked or out-of-bounds cell
+    ///
+    /// Two-pass chamfer transform (orthogonal step 1, diagonal step sqrt 2), indexed by
+    /// cell ID. Blocked cells are 0 and a free cell next to a wall is 1. Thin edges are
+    /// ignored. Cached until the next tracked edit, like the region labels.
+    pub fn distance_transform(&self) -> &[f32] {
+        self.distance_cache.get_or_init(|| self.compute_distance_transform())
+    }
+
+    fn compute_distance_transform(&self) -> Vec<f32> {
+        const DIAGONAL: f32 = std::f32::consts::SQRT_2;
+        let mut dist: Vec<f32> = self.cells.iter()
+            .map(|&value| if value == 0 { f32::INFINITY } else { 0.0 })
+            .collect();
+
+        // Out-of-bounds neighbors count as walls
+        let relax = |dist: &mut Vec<f32>, x: i32, y: i32, neighbors: &[(i32, i32, f32)]| {
+            let id = self.get_id(x, y) as usize;
+            for &(dx, dy, cost) in neighbors {
+                let (nx, ny) = (x + dx, y + dy);
+                let base = if nx < 0 || nx >= self.cols || ny < 0 || ny >= self.rows {
+                    0.0
+                } else {
+                    dist[self.get_id(nx, ny) as usize]
+                };
+                dist[id] = dist[id].min(base + cost);
+            }
+        };
+
+        let forward = [(-1, 0, 1.0), (0, -1, 1.0), (-1, -1, DIAGONAL), (1, -1, DIAGONAL)];
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                relax(&mut dist, x, y, &forward);
+            }
+        }
+        let backward = [(1, 0, 1.0), (0, 1, 1.0), (1, 1, DIAGONAL), (-1, 1, DIAGONAL)];
+        for y in (0..self.rows).rev() {
+            for x in (0..self.cols).rev() {
+                relax(&mut dist, x, y, &backward);
+            }
         }
+        dist
+    }
+
+    /// Block or unblock the edge on side `dir` of cell (x, y)
+    /// The matching edge of the neighboring cell is updated too, so the wall is two-sided
+    /// Setting an edge to its current state is not a change (no revision bump, nothing dirty)
+    pub fn set_edge(&mut self, x: i32, y: i32, dir: Direction, blocked: bool) {
+        let (dx, dy) = dir.offset();
+        let mut changed = false;
+        for (cx, cy, d) in [(x, y, dir), (x + dx, y + dy, dir.opposite())] {
+            if cx >= 0 && cx < self.cols && cy >= 0 && cy < self.rows {
+                let id = self.get_id(cx, cy) as usize;
+                let updated = if blocked { self.edges[id] | d.bit() } else { self.edges[id] & !d.bit() };
+                if updated != self.edges[id] {
+                    self.edges[id] = updated;
+                    self.mark_dirty(cx, cy);
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.bump_revision();
+        }
+    }
+
+    /// Check if the edge on side `dir` of cell (x, y) is blocked
+    pub fn is_edge_blocked(&self, x: i32, y: i32, dir: Direction) -> bool {
+        if x < 0 || x >= self.cols || y < 0 || y >= self.rows {
+            return true;
+        }
+        self.edges[self.get_id(x, y) as usize] & dir.bit() != 0
+    }
+
+    /// Check if a single orthogonal step from (x, y) towards `dir` is allowed
+    /// The target cell must be free and the shared edge must not be blocked
+    pub fn can_step(&self, x: i32, y: i32, dir: Direction) -> bool {
+        let (dx, dy) = dir.offset();
+        !self.is_edge_blocked(x, y, dir) && !self.is_blocked(x + dx, y + dy)
+    }
+}
+
+/// Directions towards decreasing and increasing coordinates along a unit axis
+fn axis_directions(axis: (i32, i32)) -> (Direction, Direction) {
+    if axis.0 != 0 {
+        (Direction::West, Direction::East)
+    } else {
+        (Direction::North, Direction::South)
+    }
+}
+
+/// Cut vertices of an undirected graph (Tarjan, iterative to avoid deep recursion)
+fn articulation_points(adjacency: &[Vec<usize>]) -> Vec<bool> {
+    const UNVISITED: usize = usize::MAX;
+    let mut discovered = vec![UNVISITED; adjacency.len()];
+    let mut low = vec![0; adjacency.len()];
+    let mut is_cut = vec![false; adjacency.len()];
+    let mut time = 0;
+
+    for root in 0..adjacency.len() {
+        if discovered[root] != UNVISITED {
+            continue;
+        }
+        discovered[root] = time;
+        low[root] = time;
+        time += 1;
+        let mut root_children = 0;
+        // (node, parent, index of the next neighbor to visit)
+        let mut stack = vec![(root, UNVISITED, 0)];
+
+        while let Some(&(node, parent, next)) = stack.last() {
+            if let Some(&neighbor) = adjacency[node].get(next) {
+                if let Some(top) = stack.last_mut() {
+                    top.2 += 1;
+                }
+                if discovered[neighbor] == UNVISITED {
+                    discovered[neighbor] = time;
+                    low[neighbor] = time;
+                    time += 1;
+                    if node == root {
+                        root_children += 1;
+                    }
+                    stack.push((neighbor, node, 0));
+                } else if neighbor != parent {
+                    low[node] = low[node].min(discovered[neighbor]);
+                }
+            } else {
+                stack.pop();
+                if parent != UNVISITED {
+                    low[parent] = low[parent].min(low[node]);
+                    if parent != root && low[node] >= discovered[parent] {
+                        is_cut[parent] = true;
+                    }
+                }
+            }
+        }
+        is_cut[root] = root_children > 1;
+    }
+    is_cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Grid>();
+    }
+
+    #[test]
+    fn test_cell_id_round_trip() {
+        let grid = Grid::new(3, 5);
+        for y in 0..grid.rows {
+            for x in 0..grid.cols {
+                let id = grid.id(x, y);
+                assert_eq!(id.0, grid.get_id(x, y));
+                assert_eq!(grid.coords(id), (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_blocked_edge_is_two_sided() {
+        let mut grid = Grid::new(5, 5);
+        grid.set_edge(2, 2, Direction::North, true);
+
+        assert!(grid.is_edge_blocked(2, 2, Direction::North));
+        assert!(grid.is_edge_blocked(2, 1, Direction::South));
+        assert!(!grid.is_blocked(2, 1));
+
+        grid.set_edge(2, 1, Direction::South, false);
+        assert!(!grid.is_edge_blocked(2, 2, Direction::North));
+    }
+
+    #[test]
+    fn test_step_around_blocked_edge() {
+        let mut grid = Grid::new(5, 5);
+        grid.set_edge(2, 2, Direction::North, true);
+
+        // Both cells are free, but the direct step north crosses the thin wall
+        assert!(!grid.can_step(2, 2, Direction::North));
+
+        // Going around: east, north, west
+        assert!(grid.can_step(2, 2, Direction::East));
+        assert!(grid.can_step(3, 2, Direction::North));
+        assert!(grid.can_step(3, 1, Direction::West));
+    }
+
+    #[test]
+    fn test_line_segment_bounded_by_walls() {
+        let grid = Grid::with_blocked(2, 10, &[2, 7]);
+        let segment = grid.line_segment_at(4, 0).unwrap();
+        assert_eq!(segment, LineSegment { y: 0, start_x: 3, end_x: 6 });
+        assert_eq!(segment.width(), 4);
+        assert!(segment.contains(3) && segment.contains(6));
+        assert!(!segment.contains(2) && !segment.contains(7));
+        assert_eq!(grid.line_segment_at(2, 0), None);
+    }
+
+    #[test]
+    fn test_line_segment_bounded_by_grid_edges() {
+        let grid = Grid::with_blocked(2, 10, &[2, 7]);
+        assert_eq!(grid.line_segment_at(0, 0), Some(LineSegment { y: 0, start_x: 0, end_x: 1 }));
+        assert_eq!(grid.line_segment_at(9, 0), Some(LineSegment { y: 0, start_x: 8, end_x: 9 }));
+        assert_eq!(grid.line_segment_at(5, 1), Some(LineSegment { y: 1, start_x: 0, end_x: 9 }));
+        assert_eq!(grid.line_segment_at(10, 1), None);
+    }
+
+    #[test]
+    fn test_line_segment_bounded_by_thin_edges() {
+        let mut grid = Grid::new(2, 10);
+        grid.set_edge(4, 0, Direction::East, true);
+        assert_eq!(grid.line_segment_at(2, 0), Some(LineSegment { y: 0, start_x: 0, end_x: 4 }));
+        assert_eq!(grid.line_segment_at(5, 0), Some(LineSegment { y: 0, start_x: 5, end_x: 9 }));
+        assert_eq!(grid.line_segment_at(5, 1).map(|segment| segment.width()), Some(10));
+    }
+
+    #[test]
+    fn test_corridor_width_through_pinch() {
+        // 5 rows open, except column 4 which only has a gap at row 2
+        let grid = Grid::with_blocked(5, 9, &[4, 13, 31, 40]);
+
+        assert_eq!(grid.min_corridor_width_along(&[(0, 2), (8, 2)]), 1);
+        assert_eq!(grid.min_corridor_width_along(&[(0, 2), (3, 2)]), 5);
+        // Vertical travel measures horizontally
+        assert_eq!(grid.min_corridor_width_along(&[(6, 0), (6, 4)]), 4);
+    }
+
+    #[test]
+    fn test_inflated_closes_narrow_corridor() {
+        // 1-wide horizontal corridor at row 2, 3-wide open room below it
+        let mut blocked: Vec<i32> = (0..10).chain(10..20).chain(30..40).collect();
+        blocked.retain(|&id| id != 32);
+        let grid = Grid::with_blocked(7, 10, &blocked);
+
+        let inflated = grid.inflated(1);
+        assert!((0..10).all(|x| inflated.is_blocked(x, 2)));
+        // Room centre (row 5) is 2 cells from any wall and stays free
+        assert!(!inflated.is_blocked(5, 5));
+        assert!(inflated.is_blocked(5, 4));
+        // Original grid is untouched
+        assert!(!grid.is_blocked(5, 2));
+    }
+
+    #[test]
+    fn test_set_cell_marks_one_chunk_dirty() {
+        let mut grid = Grid::new(20, 20);
+        assert_eq!(grid.dirty_chunks().count(), 9);
+        grid.clear_dirty();
+
+        grid.set_cell(10, 17, 1);
+        assert_eq!(grid.dirty_chunks().collect::<Vec<_>>(), vec![(1, 2)]);
+        assert_eq!(grid.revision, 1);
+
+        // Writing the same value again is not a change
+        grid.clear_dirty();
+        grid.set_cell(10, 17, 1);
+        assert_eq!(grid.dirty_chunks().count(), 0);
+        assert_eq!(grid.revision, 1);
+
+        // Same for edges: clearing an open edge changes nothing
+        grid.set_edge(2, 2, Direction::North, false);
+        assert_eq!(grid.dirty_chunks().count(), 0);
+        assert_eq!(grid.revision, 1);
+
+        grid.set_edge(2, 2, Direction::North, true);
+        assert_eq!(grid.revision, 2);
+        grid.clear_dirty();
+        grid.set_edge(2, 1, Direction::South, true);
+        assert_eq!(grid.dirty_chunks().count(), 0);
+        assert_eq!(grid.revision, 2);
+    }
+
+    #[test]
+    fn test_walkable_cells_are_free() {
+        let grid = Grid::with_blocked(4, 5, &[0, 6, 7, 19]);
+        let cells: Vec<_> = grid.walkable_cells().collect();
+
+        assert_eq!(cells.len(), 16);
+        assert!(cells.iter().all(|&(x, y)| !grid.is_blocked(x, y)));
+    }
+
+    #[test]
+    fn test_random_walkable_is_seeded() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let grid = Grid::with_blocked(6, 6, &[1, 2, 3, 14, 15, 30]);
+        let picks = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..10).map(|_| grid.random_walkable(&mut rng).unwrap()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(picks(7), picks(7));
+        assert!(picks(7).iter().all(|&(x, y)| !grid.is_blocked(x, y)));
+
+        let full = Grid::with_blocked(2, 2, &[0, 1, 2, 3]);
+        assert_eq!(full.random_walkable(&mut StdRng::seed_from_u64(7)), None);
+    }
+
+    #[test]
+    fn test_flood_fill_respects_walls_and_edges() {
+        // Column 2 is a wall except at row 1
+        let mut grid = Grid::with_blocked(3, 5, &[2, 12]);
+        assert_eq!(grid.flood_fill_region(0, 0).len(), 13);
+
+        grid.set_edge(1, 1, Direction::East, true);
+        assert_eq!(grid.flood_fill_region(0, 0).len(), 6);
+        assert!(grid.flood_fill_region(2, 0).is_empty());
+    }
+
+    #[test]
+    fn test_find_gaps_two_rooms_one_door() {
+        // Two 5x3 rooms separated by wall row 3 with a door at (2, 3)
+        let grid = Grid::with_blocked(7, 5, &[15, 16, 18, 19]);
+        assert_eq!(grid.find_gaps(), vec![((2, 3), 1)]);
+
+        // Same layout with a 2-wide door reports its width
+        let wide = Grid::with_blocked(7, 5, &[15, 16, 19]);
+        assert_eq!(wide.find_gaps(), vec![((2, 3), 2)]);
+
+        // A 1-wide corridor through a thick wall is reported once
+        let corridor = Grid::with_blocked(9, 5, &[15, 16, 18, 19, 20, 21, 23, 24, 25, 26, 28, 29]);
+        assert_eq!(corridor.find_gaps(), vec![((2, 3), 1)]);
+
+        // Open grid has no gaps
+        assert!(Grid::new(5, 5).find_gaps().is_empty());
+    }
+
+    #[test]
+    fn test_find_gaps_respects_blocked_edges() {
+        // Wall row 3 with a 5-wide opening; thin edges turn its left two cells into
+        // an alcove that only opens downwards, so the real door is (3..=5, 3)
+        let mut grid = Grid::with_blocked(8, 7, &[21, 27]);
+        grid.set_edge(2, 3, Direction::East, true);
+        grid.set_edge(1, 3, Direction::North, true);
+        grid.set_edge(2, 3, Direction::North, true);
+
+        assert_eq!(grid.find_gaps(), vec![((4, 3), 3)]);
+        assert_eq!(grid.min_corridor_width_along(&[(4, 1), (4, 5)]), 3);
+    }
+
+    #[test]
+    fn test_region_labels_follow_edits() {
+        // Column 2 is a wall except at row 1
+        let mut grid = Grid::with_blocked(3, 5, &[2, 12]);
+        assert!(grid.same_region((0, 0), (4, 2)));
+        assert_eq!(grid.region_of(2, 0), None);
+
+        // Closing the gap splits the grid in two
+        grid.set_cell(2, 1, 1);
+        assert!(!grid.same_region((0, 0), (4, 2)));
+        assert_ne!(grid.region_of(0, 0), grid.region_of(4, 0));
+
+        // Reopening merges them again
+        grid.set_cell(2, 1, 0);
+        assert!(grid.same_region((0, 0), (4, 2)));
+    }
+
+    #[test]
+    fn test_set_cells_bumps_revision_once() {
+        let mut grid = Grid::new(20, 20);
+        grid.clear_dirty();
+
+        let changes: Vec<(i32, i32, i32)> = (0..20).map(|i| (i, i, 1)).chain([(25, 0, 1)]).collect();
+        assert_eq!(grid.set_cells(&changes).len(), 20);
+        assert_eq!(grid.revision, 1);
+        assert_eq!(grid.dirty_chunks().count(), 3);
+
+        assert_eq!(grid.set_cells(&[(0, 1, 1)]).len(), 1);
+        assert_eq!(grid.revision, 2);
+
+        // Nothing changed, nothing to recompute
+        assert!(grid.set_cells(&changes).is_empty());
+        assert!(grid.paint_line(0, 0, 19, 19, 1).is_empty());
+        assert_eq!(grid.revision, 2);
+    }
+
+    #[test]
+    fn test_paint_line_fills_skipped_cells() {
+        let mut grid = Grid::new(10, 10);
+        // Two far-apart consecutive mouse samples
+        let changed = grid.paint_line(1, 2, 8, 6, 1);
+
+        assert_eq!(changed.len(), 8);
+        for (x, y) in CellLine::new(1, 2, 8, 6) {
+            assert!(grid.is_blocked(x, y));
+        }
+        // Repainting changes nothing
+        assert!(grid.paint_line(1, 2, 8, 6, 1).is_empty());
+    }
+
+    #[test]
+    fn test_overlay_ring_with_clipping() {
+        // 3x3 wall ring with a free centre
+        let ring = Grid::with_blocked(3, 3, &[0, 1, 2, 3, 5, 6, 7, 8]);
+
+        let mut grid = Grid::with_blocked(6, 6, &[21]);
+        grid.overlay(&ring, 2, 2, OverlayMode::Or);
+        assert!(grid.is_blocked(2, 2) && grid.is_blocked(4, 4) && grid.is_blocked(3, 2));
+        // Or keeps the existing wall under the free centre
+        assert!(grid.is_blocked(3, 3));
+        assert_eq!(grid.cells.iter().filter(|&&c| c == 1).count(), 9);
+
+        grid.overlay(&ring, 2, 2, OverlayMode::Replace);
+        assert!(!grid.is_blocked(3, 3));
+
+        grid.overlay(&ring, 2, 2, OverlayMode::AndNot);
+        assert!(grid.cells.iter().all(|&c| c == 0));
+
+        // Stamp hanging off the bottom-right corner is clipped
+        let revision = grid.revision;
+        grid.overlay(&ring, 4, 4, OverlayMode::Or);
+        assert_eq!(grid.cells.iter().filter(|&&c| c == 1).count(), 3);
+        assert!(grid.is_blocked(4, 4) && grid.is_blocked(5, 4) && grid.is_blocked(4, 5));
+        assert!(grid.revision > revision);
+    }
+
+    #[test]
+    fn test_resize_keeps_overlap() {
+        let mut grid = Grid::with_blocked(4, 4, &[5, 15]);
+        grid.resize(3, 5);
+
+        assert_eq!(grid.cells.len(), 15);
+        assert!(grid.is_blocked(1, 1));
+        assert!(!grid.is_blocked(4, 2));
+    }
+
+    #[test]
+    fn test_distance_transform_peaks_in_center() {
+        let border: Vec<i32> = (0..49).filter(|id| id % 7 == 0 || id % 7 == 6 || id / 7 == 0 || id / 7 == 6).collect();
+        let mut grid = Grid::with_blocked(7, 7, &border);
+
+        let center = grid.get_id(3, 3) as usize;
+        let dist = grid.distance_transform();
+        assert_eq!(dist[center], 3.0);
+        assert!(dist.iter().all(|&d| d <= dist[center]));
+        assert_eq!(dist[grid.get_id(1, 3) as usize], 1.0);
+        assert_eq!(dist[0], 0.0);
+
+        // A cache hit hands out the same buffer instead of a copy
+        assert!(std::ptr::eq(dist, grid.distance_transform()));
+
+        // Recomputed after an edit
+        grid.set_cell(3, 3, 1);
+        assert_eq!(grid.distance_transform()[center], 0.0);
+    }
+
+    #[test]
+    fn test_rotate_four_times_is_identity() {
+        let mut grid = Grid::with_blocked(3, 5, &[1, 7, 13]);
+        grid.set_edge(3, 0, Direction::South, true);
+        grid.set_edge(0, 2, Direction::East, true);
+
+        let rotated = grid.rotated_90();
+        assert_eq!((rotated.rows, rotated.cols), (5, 3));
+        let (x, y) = grid.rotated_90_coords(2, 1);
+        assert!(rotated.is_blocked(x, y));
+        let (x, y) = grid.rotated_90_coords(3, 0);
+        assert!(rotated.is_edge_blocked(x, y, Direction::West));
+
+        let back = rotated.rotated_90().rotated_90().rotated_90();
+        assert_eq!((back.rows, back.cols), (grid.rows, grid.cols));
+        assert_eq!(back.cells, grid.cells);
+        assert_eq!(back.edges, grid.edges);
+    }
+
+    #[test]
+    fn test_mirror_twice_is_identity() {
+        let mut grid = Grid::with_blocked(4, 6, &[0, 9, 22]);
+        grid.set_edge(1, 1, Direction::East, true);
+
+        let mirrored = grid.mirrored(MirrorAxis::Horizontal);
+        assert!(mirrored.is_blocked(5, 0));
+        assert!(mirrored.is_edge_blocked(4, 1, Direction::West));
+        assert!(grid.mirrored(MirrorAxis::Vertical).is_blocked(0, 3));
+
+        for axis in [MirrorAxis::Horizontal, MirrorAxis::Vertical] {
+            let back = grid.mirrored(axis).mirrored(axis);
+            assert_eq!(back.cells, grid.cells);
+            assert_eq!(back.edges, grid.edges);
+        }
+    }
+
+    #[test]
+    fn test_resize_negative_dimensions_is_empty() {
+        let mut grid = Grid::with_blocked(3, 3, &[4]);
+        grid.resize(-1, 2);
+        assert_eq!((grid.rows, grid.cols), (0, 2));
+        assert!(grid.cells.is_empty());
+        assert_eq!(grid.clamp_position(5, 5), None);
+
+        grid.resize(2, 2);
+        assert_eq!(grid.walkable_cells().count(), 4);
+    }
+
+    #[test]
+    fn test_clamp_observer_after_shrink() {
+        let mut grid = Grid::new(10, 10);
+        let (observer_x, observer_y) = (9, 9);
+
+        grid.resize(5, 6);
+        let (observer_x, observer_y) = grid.clamp_position(observer_x, observer_y).unwrap();
+        assert_eq!((observer_x, observer_y), (5, 4));
+
+        let visible = crate::raycast(&grid, observer_x, observer_y);
+        assert_eq!(visible.len(), 30);
     }
 }
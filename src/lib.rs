@@ -1,7 +1,18 @@
 pub mod cone;
+pub mod fixture;
 pub mod grid;
+pub mod line;
+pub mod palette;
 pub mod ray;
 pub mod raycast;
+pub mod text_map;
 
-pub use grid::Grid;
-pub use raycast::raycast;
+pub use fixture::RaycastTestData;
+pub use grid::{CellId, Direction, Grid, LineSegment, MirrorAxis, OverlayMode};
+pub use line::CellLine;
+pub use palette::{visibility_color, CellKind};
+pub use raycast::{
+    cells_that_see, compute_frontier, raycast, raycast_delta, raycast_frontier, raycast_into, raycast_union,
+    raycast_with_edges, VisibilityDelta,
+};
+pub use text_map::{AsciiError, GridAscii, RleError};
@@ -0,0 +1,83 @@
+/// Iterator over the grid cells on a line between two cells (Bresenham)
+/// Both endpoints are included and consecutive cells are 8-connected.
+#[derive(Debug, Clone)]
+pub struct CellLine {
+    x: i32,
+    y: i32,
+    end_x: i32,
+    end_y: i32,
+    dx: i32,
+    dy: i32,
+    step_x: i32,
+    step_y: i32,
+    err: i32,
+    done: bool,
+}
+
+impl CellLine {
+    /// Create a line from (x0, y0) to (x1, y1)
+    pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        CellLine {
+            x: x0,
+            y: y0,
+            end_x: x1,
+            end_y: y1,
+            dx,
+            dy,
+            step_x: if x0 < x1 { 1 } else { -1 },
+            step_y: if y0 < y1 { 1 } else { -1 },
+            err: dx + dy,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for CellLine {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<(i32, i32)> {
+        if self.done {
+            return None;
+        }
+
+        let current = (self.x, self.y);
+        if self.x == self.end_x && self.y == self.end_y {
+            self.done = true;
+            return Some(current);
+        }
+
+        let doubled = 2 * self.err;
+        if doubled >= self.dy {
+            self.err += self.dy;
+            self.x += self.step_x;
+        }
+        if doubled <= self.dx {
+            self.err += self.dx;
+            self.y += self.step_y;
+        }
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_is_continuous() {
+        let cells: Vec<_> = CellLine::new(1, 1, 8, 4).collect();
+        assert_eq!(cells.first(), Some(&(1, 1)));
+        assert_eq!(cells.last(), Some(&(8, 4)));
+        assert_eq!(cells.len(), 8);
+        for pair in cells.windows(2) {
+            assert!((pair[1].0 - pair[0].0).abs() <= 1 && (pair[1].1 - pair[0].1).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn test_single_cell_line() {
+        assert_eq!(CellLine::new(3, 3, 3, 3).collect::<Vec<_>>(), vec![(3, 3)]);
+    }
+}
@@ -1,16 +1,28 @@
 use arboard::Clipboard;
 use macroquad::prelude::*;
-use rustgame3::{Grid, raycast};
+use rustgame3::{CellKind, Grid, MirrorAxis, RaycastTestData, raycast, raycast_delta, raycast_into, raycast_union, visibility_color};
 use std::collections::HashSet;
 
+/// Whole-scene editor transform
+#[derive(Clone, Copy)]
+enum SceneTransform {
+    /// 90 degrees clockwise
+    Rotate,
+    Mirror(MirrorAxis),
+}
+
 /// Visualization state
 struct VisState {
     grid: Grid,
     observer_x: i32,
     observer_y: i32,
+    /// Additional light sources; visibility is the union over all observers
+    extra_observers: Vec<(i32, i32)>,
     visible_cells: HashSet<i32>,
     cell_width: f32,
     cell_height: f32,
+    /// Cell painted on the previous frame of a Shift+drag stroke
+    last_paint_cell: Option<(i32, i32)>,
 }
 
 impl VisState {
@@ -24,9 +36,11 @@ impl VisState {
             grid,
             observer_x,
             observer_y,
+            extra_observers: Vec::new(),
             visible_cells,
             cell_width: 20.0,
             cell_height: 15.0,
+            last_paint_cell: None,
         }
     }
 
@@ -39,23 +53,89 @@ impl VisState {
 
             // Left click: toggle obstacle
             if is_mouse_button_pressed(MouseButton::Left) {
-                let current = self.grid.cells[cell_id as usize];
-                self.grid.cells[cell_id as usize] = if current == 1 { 0 } else { 1 };
-                self.update_visible();
+                let current = self.grid.get_cell(grid_x, grid_y);
+                self.grid.set_cell(grid_x, grid_y, if current == 1 { 0 } else { 1 });
+                self.update_visible_after_edit(&[cell_id]);
             }
             // Right click: move observer
-            else if is_mouse_button_pressed(MouseButton::Right) {
-                if !self.grid.is_blocked(grid_x, grid_y) {
-                    self.observer_x = grid_x;
-                    self.observer_y = grid_y;
-                    self.update_visible();
-                }
+            else if is_mouse_button_pressed(MouseButton::Right) && !self.grid.is_blocked(grid_x, grid_y) {
+                self.observer_x = grid_x;
+                self.observer_y = grid_y;
+                self.update_visible();
             }
         }
     }
 
+    /// Shift+drag: paint walls along the segment since the last sampled cell,
+    /// so fast strokes don't leave gaps between frames
+    fn handle_paint_drag(&mut self, mouse_x: f32, mouse_y: f32) {
+        let grid_x = (mouse_x / self.cell_width) as i32;
+        let grid_y = (mouse_y / self.cell_height) as i32;
+        let (from_x, from_y) = self.last_paint_cell.unwrap_or((grid_x, grid_y));
+        self.last_paint_cell = Some((grid_x, grid_y));
+
+        let mut changed = self.grid.paint_line(from_x, from_y, grid_x, grid_y, 1);
+
+        // Never wall in the observer
+        let observer_id = self.grid.get_id(self.observer_x, self.observer_y);
+        if changed.contains(&observer_id) {
+            self.grid.set_cell(self.observer_x, self.observer_y, 0);
+            changed.retain(|&id| id != observer_id);
+        }
+
+        if !changed.is_empty() {
+            self.update_visible_after_edit(&changed);
+        }
+    }
+
+    /// L key: add a light source at the mouse cell, or remove the one already there
+    fn toggle_extra_observer(&mut self, mouse_x: f32, mouse_y: f32) {
+        let grid_x = (mouse_x / self.cell_width) as i32;
+        let grid_y = (mouse_y / self.cell_height) as i32;
+
+        if let Some(index) = self.extra_observers.iter().position(|&o| o == (grid_x, grid_y)) {
+            self.extra_observers.remove(index);
+        } else if !self.grid.is_blocked(grid_x, grid_y) {
+            self.extra_observers.push((grid_x, grid_y));
+        }
+        self.update_visible();
+    }
+
+    /// Rotate (R) or mirror (M, Shift+M) the whole scene, remapping every observer
+    fn transform_scene(&mut self, transform: SceneTransform) {
+        let map = |grid: &Grid, (x, y): (i32, i32)| match transform {
+            SceneTransform::Rotate => grid.rotated_90_coords(x, y),
+            SceneTransform::Mirror(axis) => grid.mirrored_coords(axis, x, y),
+        };
+
+        (self.observer_x, self.observer_y) = map(&self.grid, (self.observer_x, self.observer_y));
+        self.extra_observers = self.extra_observers.iter().map(|&o| map(&self.grid, o)).collect();
+        self.grid = match transform {
+            SceneTransform::Rotate => self.grid.rotated_90(),
+            SceneTransform::Mirror(axis) => self.grid.mirrored(axis),
+        };
+        self.update_visible();
+    }
+
     fn update_visible(&mut self) {
-        self.visible_cells = raycast(&self.grid, self.observer_x, self.observer_y);
+        if self.extra_observers.is_empty() {
+            raycast_into(&self.grid, self.observer_x, self.observer_y, &mut self.visible_cells);
+        } else {
+            let mut observers = vec![(self.observer_x, self.observer_y)];
+            observers.extend(self.extra_observers.iter().copied());
+            self.visible_cells = raycast_union(&self.grid, &observers);
+        }
+    }
+
+    /// Refresh visibility after grid edits; the incremental path only applies to a single observer
+    fn update_visible_after_edit(&mut self, changed: &[i32]) {
+        if self.extra_observers.is_empty() {
+            let observer = (self.observer_x, self.observer_y);
+            let delta = raycast_delta(&self.grid, observer.0, observer.1, observer, &self.visible_cells, changed);
+            delta.apply(&mut self.visible_cells);
+        } else {
+            self.update_visible();
+        }
     }
 
     fn grid_to_string(&self) -> String {
@@ -81,8 +161,9 @@ impl VisState {
         result
     }
 
-    fn copy_to_clipboard(&self) {
-        let grid_string = self.grid_to_string();
+    /// Copy the grid to the clipboard, as ASCII art or (compact) run-length encoded
+    fn copy_to_clipboard(&self, rle: bool) {
+        let grid_string = if rle { self.grid.to_rle_string() } else { self.grid_to_string() };
         match Clipboard::new() {
             Ok(mut clipboard) => {
                 if let Err(e) = clipboard.set_text(&grid_string) {
@@ -99,6 +180,27 @@ impl VisState {
         }
     }
 
+    fn export_raycast_test(&self, name: &str) -> RaycastTestData {
+        RaycastTestData::capture(name, &self.grid, self.observer_x, self.observer_y)
+    }
+
+    fn export_to_file(&self) {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let name = format!("export_{}", timestamp);
+        let path = format!("test_data/{}.json", name);
+
+        match serde_json::to_string_pretty(&self.export_raycast_test(&name)) {
+            Ok(json) => match std::fs::write(&path, json) {
+                Ok(()) => println!("Exported raycast test to {}", path),
+                Err(e) => println!("Failed to write {}: {}", path, e),
+            },
+            Err(e) => println!("Failed to serialize raycast test: {}", e),
+        }
+    }
+
     fn draw(&self) {
         clear_background(Color::from_rgba(30, 30, 30, 255));
 
@@ -109,15 +211,18 @@ impl VisState {
                 let px = x as f32 * self.cell_width;
                 let py = y as f32 * self.cell_height;
 
-                let color = if x == self.observer_x && y == self.observer_y {
-                    BLUE // Observer
+                let kind = if x == self.observer_x && y == self.observer_y {
+                    CellKind::Observer
+                } else if self.extra_observers.contains(&(x, y)) {
+                    CellKind::LightSource
                 } else if self.grid.is_blocked(x, y) {
-                    RED // Blocked
-                } else if self.visible_cells.contains(&cell_id) {
-                    Color::from_rgba(100, 200, 100, 255) // Visible
+                    CellKind::Blocked
                 } else {
-                    Color::from_rgba(60, 60, 60, 255) // Not visible
+                    CellKind::Free
                 };
+                let level = if self.visible_cells.contains(&cell_id) { 1.0 } else { 0.0 };
+                let (r, g, b, a) = visibility_color(level, kind);
+                let color = Color::from_rgba(r, g, b, a);
 
                 draw_rectangle(px, py, self.cell_width - 1.0, self.cell_height - 1.0, color);
             }
@@ -187,7 +292,7 @@ impl VisState {
 
         // Draw info
         let info = format!(
-            "Observer: ({}, {})\nVisible cells: {}\nLeft click: toggle obstacle\nRight click: move observer\nC: copy grid to clipboard\nEsc: close window",
+            "Observer: ({}, {})\nVisible cells: {}\nLeft click: toggle obstacle\nShift+drag: paint walls\nRight click: move observer\nL: toggle light at mouse\nR: rotate scene, M: mirror (Shift+M: vertical)\nC: copy grid to clipboard (Shift+C: RLE)\nE: export raycast test JSON\nEsc: close window",
             self.observer_x,
             self.observer_y,
             self.visible_cells.len()
@@ -202,15 +307,43 @@ async fn main() {
 
     loop {
         // Handle input
-        if is_mouse_button_pressed(MouseButton::Left) || is_mouse_button_pressed(MouseButton::Right)
-        {
+        let shift_down = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        if shift_down && is_mouse_button_down(MouseButton::Left) {
             let (mouse_x, mouse_y) = mouse_position();
-            state.handle_click(mouse_x, mouse_y);
+            state.handle_paint_drag(mouse_x, mouse_y);
+        } else {
+            state.last_paint_cell = None;
+
+            if is_mouse_button_pressed(MouseButton::Left) || is_mouse_button_pressed(MouseButton::Right)
+            {
+                let (mouse_x, mouse_y) = mouse_position();
+                state.handle_click(mouse_x, mouse_y);
+            }
         }
 
-        // Copy grid to clipboard on C key
+        // Copy grid to clipboard on C key (Shift+C: run-length encoded)
         if is_key_pressed(KeyCode::C) {
-            state.copy_to_clipboard();
+            state.copy_to_clipboard(shift_down);
+        }
+
+        // Toggle an extra light source at the mouse cell on L key
+        if is_key_pressed(KeyCode::L) {
+            let (mouse_x, mouse_y) = mouse_position();
+            state.toggle_extra_observer(mouse_x, mouse_y);
+        }
+
+        // Rotate the scene on R, mirror it on M (Shift+M: top-bottom)
+        if is_key_pressed(KeyCode::R) {
+            state.transform_scene(SceneTransform::Rotate);
+        }
+        if is_key_pressed(KeyCode::M) {
+            let axis = if shift_down { MirrorAxis::Vertical } else { MirrorAxis::Horizontal };
+            state.transform_scene(SceneTransform::Mirror(axis));
+        }
+
+        // Export current setup as a JSON regression test on E key
+        if is_key_pressed(KeyCode::E) {
+            state.export_to_file();
         }
 
         // Close window on Escape
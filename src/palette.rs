@@ -0,0 +1,45 @@
+/// What a cell shows as, independent of how visible it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Observer,
+    LightSource,
+    Blocked,
+    Free,
+}
+
+/// RGBA colors used for free cells
+pub const HIDDEN_COLOR: (u8, u8, u8, u8) = (60, 60, 60, 255);
+pub const VISIBLE_COLOR: (u8, u8, u8, u8) = (100, 200, 100, 255);
+
+/// Color of a cell at the given visibility level (0.0 hidden, 1.0 fully visible)
+/// Free cells blend from `HIDDEN_COLOR` to `VISIBLE_COLOR`; other kinds ignore the level.
+pub fn visibility_color(level: f32, kind: CellKind) -> (u8, u8, u8, u8) {
+    match kind {
+        CellKind::Observer => (0, 121, 241, 255),
+        CellKind::LightSource => (102, 191, 255, 255),
+        CellKind::Blocked => (230, 41, 55, 255),
+        CellKind::Free => {
+            let t = level.clamp(0.0, 1.0);
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+            (
+                mix(HIDDEN_COLOR.0, VISIBLE_COLOR.0),
+                mix(HIDDEN_COLOR.1, VISIBLE_COLOR.1),
+                mix(HIDDEN_COLOR.2, VISIBLE_COLOR.2),
+                mix(HIDDEN_COLOR.3, VISIBLE_COLOR.3),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_endpoints() {
+        assert_eq!(visibility_color(0.0, CellKind::Free), HIDDEN_COLOR);
+        assert_eq!(visibility_color(1.0, CellKind::Free), VISIBLE_COLOR);
+        assert_eq!(visibility_color(2.0, CellKind::Free), VISIBLE_COLOR);
+        assert_eq!(visibility_color(0.5, CellKind::Blocked), visibility_color(1.0, CellKind::Blocked));
+    }
+}
@@ -1,6 +1,7 @@
-use crate::grid::Grid;
+use crate::grid::{Direction, Grid};
 use crate::ray::RayState;
 use std::collections::HashSet;
+use std::ops::Deref;
 
 /// Deferred cone state (matches C# PFContextNode)
 #[derive(Clone, Debug)]
@@ -14,17 +15,66 @@ struct DeferredCone {
     prev_l_end_x: i32,
 }
 
+/// The grid as a cast sees it: cells only, or cells plus sight-blocking edges
+struct Sight<'a> {
+    grid: &'a Grid,
+    edges: bool,
+}
+
+impl Deref for Sight<'_> {
+    type Target = Grid;
+
+    fn deref(&self) -> &Grid {
+        self.grid
+    }
+}
+
+impl Sight<'_> {
+    /// True if (x, y) can't be seen from the previous row of a scan in direction `dir`
+    /// (`dir` 0 is the observer's own row, where only the cell itself matters)
+    fn blocks(&self, x: i32, y: i32, dir: i32) -> bool {
+        let facing = if dir > 0 { Direction::North } else { Direction::South };
+        self.grid.is_blocked(x, y) || (self.edges && dir != 0 && self.grid.is_edge_blocked(x, y, facing))
+    }
+}
+
 /// Main raycasting function
 pub fn raycast(grid: &Grid, start_x: i32, start_y: i32) -> HashSet<i32> {
     let mut visible = HashSet::new();
+    raycast_into(grid, start_x, start_y, &mut visible);
+    visible
+}
+
+/// Raycast into a caller-provided set, reusing its allocation
+/// The set is cleared first; results are identical to `raycast`
+///
+/// Cones end at the first row without walkable cells, so an observer in a closed room
+/// never scans past the room walls; no separate region check is needed.
+pub fn raycast_into(grid: &Grid, start_x: i32, start_y: i32, visible: &mut HashSet<i32>) {
+    cast_into(&Sight { grid, edges: false }, start_x, start_y, visible);
+}
+
+/// Raycast that also treats blocked edges (`Grid::set_edge`) as thin walls
+///
+/// Blocked East/West edges split a row into separate segments, and a cell whose edge
+/// facing the observer's side is blocked is opaque for the row it is in. The plain
+/// `raycast` ignores edges, which keeps it identical to the C# port.
+pub fn raycast_with_edges(grid: &Grid, start_x: i32, start_y: i32) -> HashSet<i32> {
+    let mut visible = HashSet::new();
+    cast_into(&Sight { grid, edges: true }, start_x, start_y, &mut visible);
+    visible
+}
+
+fn cast_into(grid: &Sight, start_x: i32, start_y: i32, visible: &mut HashSet<i32>) {
+    visible.clear();
 
     if grid.is_blocked(start_x, start_y) {
-        return visible;
+        return;
     }
 
     let mut lanes: Vec<Vec<(i32, i32)>> = vec![Vec::new(); grid.rows as usize];
 
-    let (row_start_x, row_end_x) = find_walkable_bounds(grid, start_x, start_y);
+    let (row_start_x, row_end_x) = find_walkable_bounds(grid, start_x, start_y, 0);
     lanes[start_y as usize].push((row_start_x + 1, row_end_x + 1));
 
     scan_direction(grid, start_x, start_y, 1, row_start_x, row_end_x, &mut lanes);
@@ -44,20 +94,182 @@ pub fn raycast(grid: &Grid, start_x: i32, start_y: i32) -> HashSet<i32> {
     //     }
     // }
 
-    for (row, ranges) in lanes.iter().enumerate() {
-        for &(range_start, range_end) in ranges {
+    collect_lane_cells(grid, &lanes, 0..grid.rows, visible);
+}
+
+/// Combined visibility of several observers (e.g. multiple light sources)
+/// A cell is visible if any observer sees it; blocked observers contribute nothing
+pub fn raycast_union(grid: &Grid, observers: &[(i32, i32)]) -> HashSet<i32> {
+    let mut visible = HashSet::new();
+    let mut buffer = HashSet::new();
+    for &(x, y) in observers {
+        raycast_into(grid, x, y, &mut buffer);
+        visible.extend(buffer.iter().copied());
+    }
+    visible
+}
+
+/// Visible cells plus the frontier: visible cells next to a hidden free cell
+/// Same as `raycast` followed by `compute_frontier`
+pub fn raycast_frontier(grid: &Grid, start_x: i32, start_y: i32) -> (HashSet<i32>, HashSet<i32>) {
+    let visible = raycast(grid, start_x, start_y);
+    let frontier = compute_frontier(grid, &visible);
+    (visible, frontier)
+}
+
+/// Visible cells with at least one orthogonal neighbor that is free but not visible
+/// (where a reveal animation or an exploring agent would continue)
+pub fn compute_frontier(grid: &Grid, visible: &HashSet<i32>) -> HashSet<i32> {
+    visible.iter()
+        .copied()
+        .filter(|&id| {
+            let (x, y) = grid.get_coords(id);
+            [(0, -1), (1, 0), (0, 1), (-1, 0)].iter().any(|&(dx, dy)| {
+                !grid.is_blocked(x + dx, y + dy) && !visible.contains(&grid.get_id(x + dx, y + dy))
+            })
+        })
+        .collect()
+}
+
+/// Candidate cells whose own raycast contains `target`
+///
+/// Casting once from the target is cheaper but answers "what does the target see",
+/// which differs where visibility is not symmetric (diagonal wall corners). This casts
+/// from every candidate instead, so it is slower but always uses the right direction.
+pub fn cells_that_see(grid: &Grid, target: (i32, i32), candidate_cells: &[i32]) -> HashSet<i32> {
+    let mut seers = HashSet::new();
+    if grid.is_blocked(target.0, target.1) {
+        return seers;
+    }
+
+    let target_id = grid.get_id(target.0, target.1);
+    let mut visible = HashSet::new();
+    for &candidate in candidate_cells {
+        if grid.is_blocked_id(candidate) {
+            continue;
+        }
+        let (x, y) = grid.get_coords(candidate);
+        raycast_into(grid, x, y, &mut visible);
+        if visible.contains(&target_id) {
+            seers.insert(candidate);
+        }
+    }
+    seers
+}
+
+/// Cells that became visible or hidden between two casts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VisibilityDelta {
+    pub newly_visible: HashSet<i32>,
+    pub newly_hidden: HashSet<i32>,
+}
+
+impl VisibilityDelta {
+    /// True if visibility did not change
+    pub fn is_empty(&self) -> bool {
+        self.newly_visible.is_empty() && self.newly_hidden.is_empty()
+    }
+
+    /// Apply this delta to a previously computed visible set
+    pub fn apply(&self, visible: &mut HashSet<i32>) {
+        for id in &self.newly_hidden {
+            visible.remove(id);
+        }
+        visible.extend(self.newly_visible.iter().copied());
+    }
+}
+
+/// Incrementally update visibility after some cells changed
+///
+/// Each scan direction only looks at rows further from the observer, so a change in a row
+/// below the observer can only affect that row and the rows below it (likewise above).
+/// Only the affected half of the cast is redone, and only the affected rows are diffed.
+/// A moved observer or a change in the observer's own row falls back to a full recompute.
+pub fn raycast_delta(
+    grid: &Grid,
+    start_x: i32,
+    start_y: i32,
+    prev_start: (i32, i32),
+    prev_visible: &HashSet<i32>,
+    changed_cells: &[i32],
+) -> VisibilityDelta {
+    let cell_count = grid.rows * grid.cols;
+    let changed_rows: Vec<i32> = changed_cells.iter()
+        .filter(|&&id| id >= 0 && id < cell_count)
+        .map(|&id| grid.get_coords(id).1)
+        .collect();
+
+    if prev_start != (start_x, start_y)
+        || changed_rows.contains(&start_y)
+        || grid.is_blocked(start_x, start_y)
+    {
+        let visible = raycast(grid, start_x, start_y);
+        return VisibilityDelta {
+            newly_visible: visible.difference(prev_visible).copied().collect(),
+            newly_hidden: prev_visible.difference(&visible).copied().collect(),
+        };
+    }
+
+    // First affected row in each scan direction (rows closer to the observer are untouched)
+    let down_from = changed_rows.iter().copied().filter(|&y| y > start_y).min();
+    let up_to = changed_rows.iter().copied().filter(|&y| y < start_y).max();
+    let is_affected = |y: i32| down_from.is_some_and(|from| y >= from) || up_to.is_some_and(|to| y <= to);
+
+    let sight = Sight { grid, edges: false };
+    let mut lanes: Vec<Vec<(i32, i32)>> = vec![Vec::new(); grid.rows as usize];
+    let mut fresh = HashSet::new();
+    let (row_start_x, row_end_x) = find_walkable_bounds(&sight, start_x, start_y, 0);
+
+    if let Some(from) = down_from {
+        scan_direction(&sight, start_x, start_y, 1, row_start_x, row_end_x, &mut lanes);
+        collect_lane_cells(grid, &lanes, from..grid.rows, &mut fresh);
+    }
+    if let Some(to) = up_to {
+        scan_direction(&sight, start_x, start_y, -1, row_start_x, row_end_x, &mut lanes);
+        collect_lane_cells(grid, &lanes, 0..to + 1, &mut fresh);
+    }
+
+    VisibilityDelta {
+        newly_visible: fresh.difference(prev_visible).copied().collect(),
+        newly_hidden: prev_visible.iter()
+            .copied()
+            .filter(|&id| is_affected(grid.get_coords(id).1) && !fresh.contains(&id))
+            .collect(),
+    }
+}
+
+/// Convert lane ranges (1-based, inclusive) of the given rows into visible cell IDs
+fn collect_lane_cells(grid: &Grid, lanes: &[Vec<(i32, i32)>], rows: std::ops::Range<i32>, visible: &mut HashSet<i32>) {
+    for row in rows {
+        for &(range_start, range_end) in &lanes[row as usize] {
             for x in (range_start - 1)..=(range_end - 1) {
                 if x >= 0 && x < grid.cols {
-                    visible.insert(grid.get_id(x, row as i32));
+                    visible.insert(grid.get_id(x, row));
                 }
             }
         }
     }
+}
 
-    visible
+/// Free segment of row `y` around x, as seen by a scan in direction `dir`
+fn find_walkable_bounds(grid: &Sight, x: i32, y: i32, dir: i32) -> (i32, i32) {
+    if !grid.edges {
+        return find_cell_bounds(grid, x, y);
+    }
+
+    let mut start_x = x;
+    while !grid.is_edge_blocked(start_x, y, Direction::West) && !grid.blocks(start_x - 1, y, dir) {
+        start_x -= 1;
+    }
+    let mut end_x = x;
+    while !grid.is_edge_blocked(end_x, y, Direction::East) && !grid.blocks(end_x + 1, y, dir) {
+        end_x += 1;
+    }
+    (start_x, end_x)
 }
 
-fn find_walkable_bounds(grid: &Grid, x: i32, y: i32) -> (i32, i32) {
+/// Cells-only walkable bounds (C# parity: thin edges are ignored)
+fn find_cell_bounds(grid: &Grid, x: i32, y: i32) -> (i32, i32) {
     let mut start_x = x;
     let mut end_x = x;
 
@@ -74,13 +286,13 @@ fn find_walkable_bounds(grid: &Grid, x: i32, y: i32) -> (i32, i32) {
 
 /// Scan in one direction - EXACT match to C# getBorders + stepNxt logic
 fn scan_direction(
-    grid: &Grid,
+    grid: &Sight,
     start_x: i32,
     start_y: i32,
     dir: i32,
     row_start_x: i32,
     row_end_x: i32,
-    lanes: &mut Vec<Vec<(i32, i32)>>,
+    lanes: &mut [Vec<(i32, i32)>],
 ) {
     // Debug disabled
     // let debug = grid.rows == 10 && grid.cols == 10 && start_x == 5 && start_y == 3;
@@ -94,7 +306,7 @@ fn scan_direction(
         return;  // No rows in scan direction
     }
 
-    let segments = find_all_segments_in_range(grid, next_y, row_start_x, row_end_x);
+    let segments = find_all_segments_in_range(grid, next_y, row_start_x, row_end_x, dir);
     if segments.is_empty() {
         return;  // No segments found
     }
@@ -140,12 +352,12 @@ fn scan_direction(
 
 /// Process a single cone (matches C# PFContext.stepNxt)
 fn process_cone(
-    grid: &Grid,
+    grid: &Sight,
     start_x: i32,
     start_y: i32,
     dir: i32,
     mut cone: DeferredCone,
-    lanes: &mut Vec<Vec<(i32, i32)>>,
+    lanes: &mut [Vec<(i32, i32)>],
     pfn: &mut Vec<DeferredCone>,
 ) {
     // C# do-while loop (line 66)
@@ -231,7 +443,7 @@ fn process_cone(
         }
 
         // C# lines 115-170: Segment loop - find ALL segments and handle splits
-        let segments = find_all_segments_in_range(grid, next_y, cone.curr_l_start_x, cone.curr_l_end_x);
+        let segments = find_all_segments_in_range(grid, next_y, cone.curr_l_start_x, cone.curr_l_end_x, dir);
 
         if segments.is_empty() {
             break;
@@ -265,13 +477,13 @@ fn process_cone(
 }
 
 /// Find ALL walkable segments in a row within range
-fn find_all_segments_in_range(grid: &Grid, y: i32, range_start: i32, range_end: i32) -> Vec<(i32, i32)> {
+fn find_all_segments_in_range(grid: &Sight, y: i32, range_start: i32, range_end: i32, dir: i32) -> Vec<(i32, i32)> {
     let mut segments = Vec::new();
     let mut x = range_start;
 
     while x <= range_end {
         // Skip blocked cells
-        while x <= range_end && (x < 0 || x >= grid.cols || grid.is_blocked(x, y)) {
+        while x <= range_end && (x < 0 || x >= grid.cols || grid.blocks(x, y, dir)) {
             x += 1;
         }
 
@@ -280,7 +492,7 @@ fn find_all_segments_in_range(grid: &Grid, y: i32, range_start: i32, range_end:
         }
 
         // Found walkable cell - expand to get full segment
-        let (seg_start, seg_end) = find_walkable_bounds(grid, x, y);
+        let (seg_start, seg_end) = find_walkable_bounds(grid, x, y, dir);
 
         // Only include segments that overlap with our range
         if seg_end >= range_start && seg_start <= range_end {
@@ -296,6 +508,7 @@ fn find_all_segments_in_range(grid: &Grid, y: i32, range_start: i32, range_end:
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
 
     #[test]
     fn test_empty_grid() {
@@ -310,4 +523,165 @@ mod tests {
         let visible = raycast(&grid, 5, 5);
         assert_eq!(visible.len(), 0);
     }
+
+    #[test]
+    fn test_closed_room_cast_stays_in_region() {
+        let mut grid = Grid::new(200, 200);
+        for i in 90..102 {
+            for (x, y) in [(i, 90), (i, 101), (90, i), (101, i)] {
+                grid.set_cell(x, y, 1);
+            }
+        }
+
+        let visible = raycast(&grid, 95, 95);
+        assert_eq!(visible, grid.flood_fill_region(95, 95));
+        assert_eq!(visible.len(), 100);
+    }
+
+    #[test]
+    fn test_frontier_at_corridor_bend() {
+        // Corridor along row 1 that turns down at column 9
+        let mut grid = Grid::new(6, 10);
+        for y in 0..6 {
+            for x in 0..10 {
+                let open = (y == 1) || (x == 9 && y >= 1);
+                grid.set_cell(x, y, if open { 0 } else { 1 });
+            }
+        }
+
+        let (visible, frontier) = raycast_frontier(&grid, 0, 1);
+        assert_eq!(frontier, compute_frontier(&grid, &visible));
+        let last_visible = (1..6).take_while(|&y| visible.contains(&grid.get_id(9, y))).last().unwrap();
+        assert!(last_visible < 5);
+        assert!(frontier.contains(&grid.get_id(9, last_visible)));
+        assert!(!frontier.contains(&grid.get_id(3, 1)));
+    }
+
+    #[test]
+    fn test_raycast_into_reuses_buffer() {
+        let open = Grid::new(10, 10);
+        let walled = Grid::with_blocked(10, 10, &[23, 24, 25, 26, 62, 72]);
+
+        let mut buffer = HashSet::new();
+        raycast_into(&open, 5, 5, &mut buffer);
+        assert_eq!(buffer, raycast(&open, 5, 5));
+
+        // Stale cells from the previous cast must not leak into the next one
+        raycast_into(&walled, 4, 5, &mut buffer);
+        assert_eq!(buffer, raycast(&walled, 4, 5));
+
+        raycast_into(&walled, 4, 2, &mut buffer);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_raycast_union_of_two_observers() {
+        // Column 5 is a wall, splitting the grid into two halves
+        let wall: Vec<i32> = (0..10).map(|y| 5 + y * 10).collect();
+        let grid = Grid::with_blocked(10, 10, &wall);
+
+        let left = raycast(&grid, 2, 5);
+        let right = raycast(&grid, 7, 5);
+        assert!(left.is_disjoint(&right));
+
+        let union = raycast_union(&grid, &[(2, 5), (7, 5)]);
+        assert_eq!(union, left.union(&right).copied().collect());
+        assert_eq!(union.len(), 90);
+    }
+
+    #[test]
+    fn test_cells_that_see_uses_forward_direction() {
+        // (5, 0) sees (1, 2) past the diagonal corner, but not the other way round
+        let grid = Grid::with_blocked(5, 6, &[2, 7, 19, 28]);
+        let viewer = grid.get_id(5, 0);
+        let target = (1, 2);
+        assert!(!raycast(&grid, target.0, target.1).contains(&viewer));
+
+        let candidates: Vec<i32> = (0..30).collect();
+        let seers = cells_that_see(&grid, target, &candidates);
+        assert!(seers.contains(&viewer));
+        for &id in &seers {
+            let (x, y) = grid.get_coords(id);
+            assert!(raycast(&grid, x, y).contains(&grid.get_id(target.0, target.1)));
+        }
+    }
+
+    #[test]
+    fn test_raycast_delta_matches_full_recompute() {
+        let mut rng = StdRng::seed_from_u64(12345);
+
+        for _ in 0..200 {
+            let (rows, cols) = (rng.gen_range(4..14), rng.gen_range(4..14));
+            let blocked: Vec<i32> = (0..rng.gen_range(0..rows * cols / 3)).map(|_| rng.gen_range(0..rows * cols)).collect();
+            let mut grid = Grid::with_blocked(rows, cols, &blocked);
+            let (x, y) = (rng.gen_range(0..cols), rng.gen_range(0..rows));
+            let prev_visible = raycast(&grid, x, y);
+
+            let changed: Vec<i32> = (0..rng.gen_range(1..4)).map(|_| rng.gen_range(0..rows * cols)).collect();
+            for &id in &changed {
+                let (cx, cy) = grid.get_coords(id);
+                let value = 1 - grid.get_cell(cx, cy);
+                grid.set_cell(cx, cy, value);
+            }
+
+            let delta = raycast_delta(&grid, x, y, (x, y), &prev_visible, &changed);
+            let mut updated = prev_visible.clone();
+            delta.apply(&mut updated);
+            assert_eq!(updated, raycast(&grid, x, y));
+        }
+    }
+
+    #[test]
+    fn test_raycast_with_edges_stops_at_thin_walls() {
+        // Thin wall along the top of row 3
+        let mut horizontal = Grid::new(7, 7);
+        for x in 0..7 {
+            horizontal.set_edge(x, 3, Direction::North, true);
+        }
+        assert_eq!(raycast(&horizontal, 3, 1).len(), 49);
+        let visible = raycast_with_edges(&horizontal, 3, 1);
+        assert_eq!(visible, horizontal.flood_fill_region(3, 1));
+        assert_eq!(visible.len(), 21);
+
+        // Thin wall along the east side of column 3, crossing the observer's row
+        let mut vertical = Grid::new(7, 7);
+        for y in 0..7 {
+            vertical.set_edge(3, y, Direction::East, true);
+        }
+        let visible = raycast_with_edges(&vertical, 1, 3);
+        assert_eq!(visible, vertical.flood_fill_region(1, 3));
+        assert_eq!(visible.len(), 28);
+
+        // Reopening one edge makes a door; only cells in line with it are visible
+        horizontal.set_edge(3, 3, Direction::North, false);
+        let visible = raycast_with_edges(&horizontal, 3, 1);
+        assert!(visible.contains(&horizontal.get_id(3, 6)));
+        assert!(!visible.contains(&horizontal.get_id(0, 6)));
+        assert!(!visible.contains(&horizontal.get_id(6, 6)));
+    }
+
+    #[test]
+    fn test_raycast_with_edges_matches_raycast_without_edges() {
+        let mut rng = StdRng::seed_from_u64(777);
+
+        for _ in 0..200 {
+            let (rows, cols) = (rng.gen_range(4..14), rng.gen_range(4..14));
+            let blocked: Vec<i32> = (0..rng.gen_range(0..rows * cols / 3)).map(|_| rng.gen_range(0..rows * cols)).collect();
+            let grid = Grid::with_blocked(rows, cols, &blocked);
+            let (x, y) = (rng.gen_range(0..cols), rng.gen_range(0..rows));
+            assert_eq!(raycast_with_edges(&grid, x, y), raycast(&grid, x, y));
+        }
+    }
+
+    #[test]
+    fn test_raycast_delta_observer_move() {
+        let grid = Grid::with_blocked(10, 10, &[23, 24, 25, 26]);
+        let prev_visible = raycast(&grid, 5, 5);
+
+        let delta = raycast_delta(&grid, 4, 0, (5, 5), &prev_visible, &[]);
+        let mut updated = prev_visible.clone();
+        delta.apply(&mut updated);
+        assert_eq!(updated, raycast(&grid, 4, 0));
+        assert!(raycast_delta(&grid, 5, 5, (5, 5), &prev_visible, &[]).is_empty());
+    }
 }
@@ -0,0 +1,387 @@
+use crate::grid::Grid;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Text map format (same characters as the standard test files and the clipboard export)
+///
+/// | Char | Meaning            |
+/// |------|--------------------|
+/// | `s`  | observer (free)    |
+/// | `■`  | blocked cell       |
+/// | `□`  | free cell          |
+/// | `o`  | visible free cell  |
+///
+/// The grid is one block of consecutive non-blank lines; every row must have the same
+/// width. Blank lines before and after it are allowed. `Grid::from_ascii` rejects anything
+/// after the blank line that ends the block, while `Grid::from_ascii_with_commentary`
+/// (used for the annotated test files) ignores it.
+///
+/// The observer is one `s` cell, or a messy observer spanning two cells horizontally
+/// (messy X), two vertically (messy Y) or a 2x2 block (both).
+pub const LEGEND: [char; 4] = [OBSERVER, BLOCKED, FREE, VISIBLE];
+
+const OBSERVER: char = 's';
+const BLOCKED: char = '■';
+const FREE: char = '□';
+const VISIBLE: char = 'o';
+
+/// Grid parsed from a text map
+#[derive(Clone)]
+pub struct GridAscii {
+    pub grid: Grid,
+    /// Top-left observer cell (marked with `s`), if any
+    pub observer: Option<(i32, i32)>,
+    /// Observer spans two columns
+    pub messy_x: bool,
+    /// Observer spans two rows
+    pub messy_y: bool,
+    /// Cells marked visible (`o` and `s`)
+    pub visible: HashSet<i32>,
+}
+
+/// Error produced by the text map parser (lines and columns are 1-based)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsciiError {
+    /// No grid rows found
+    Empty,
+    /// Character outside the documented legend
+    UnexpectedChar { line: usize, column: usize, ch: char },
+    /// Row width differs from the first row
+    RaggedLine { line: usize, expected: usize, found: usize },
+    /// `s` cells don't form a 1x1, 2x1, 1x2 or 2x2 block
+    InvalidObserver { cells: usize },
+    /// Non-blank line after the blank line that ended the grid
+    ContentAfterGrid { line: usize },
+}
+
+impl fmt::Display for AsciiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsciiError::Empty => write!(f, "map contains no grid rows"),
+            AsciiError::UnexpectedChar { line, column, ch } => {
+                write!(f, "unexpected character '{}' at line {}, column {}", ch, line, column)
+            }
+            AsciiError::RaggedLine { line, expected, found } => {
+                write!(f, "line {} has width {}, expected {}", line, found, expected)
+            }
+            AsciiError::InvalidObserver { cells } => {
+                write!(f, "{} observer cells do not form a single, 2x1, 1x2 or 2x2 block", cells)
+            }
+            AsciiError::ContentAfterGrid { line } => {
+                write!(f, "line {} follows a blank line after the grid", line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsciiError {}
+
+/// Top-left observer cell plus messy X / messy Y flags
+type ObserverBlock = ((i32, i32), bool, bool);
+
+/// Work out the observer block from the `s` cells (row-major order)
+fn detect_observer(cells: &[(i32, i32)]) -> Result<Option<ObserverBlock>, AsciiError> {
+    let Some(&(x, y)) = cells.first() else {
+        return Ok(None);
+    };
+
+    let block = |w: i32, h: i32| -> Vec<(i32, i32)> {
+        (0..h).flat_map(|dy| (0..w).map(move |dx| (x + dx, y + dy))).collect()
+    };
+    for (w, h) in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+        if cells == block(w, h).as_slice() {
+            return Ok(Some(((x, y), w == 2, h == 2)));
+        }
+    }
+    Err(AsciiError::InvalidObserver { cells: cells.len() })
+}
+
+/// Error produced when decoding a run-length encoded grid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RleError {
+    /// Missing or malformed `<rows>x<cols>:` header
+    Header,
+    /// Unknown cell symbol at the given character offset
+    UnexpectedChar { offset: usize, ch: char },
+    /// Runs don't add up to rows * cols
+    LengthMismatch { expected: usize, found: usize },
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RleError::Header => write!(f, "expected a '<rows>x<cols>:' header"),
+            RleError::UnexpectedChar { offset, ch } => {
+                write!(f, "unexpected character '{}' at offset {}", ch, offset)
+            }
+            RleError::LengthMismatch { expected, found } => {
+                write!(f, "runs cover {} cells, expected {}", found, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RleError {}
+
+fn rle_symbol(value: i32) -> char {
+    match value {
+        0 => '.',
+        -1 => '~',
+        _ => '#',
+    }
+}
+
+impl Grid {
+    /// Canonical text map constructor (see `LEGEND` for the format)
+    /// Returns the grid plus the detected observer/messy state and the marked visible cells.
+    pub fn from_ascii(text: &str) -> Result<GridAscii, AsciiError> {
+        Self::parse_ascii(text, false)
+    }
+
+    /// Same as `from_ascii`, but everything after the blank line that ends the grid
+    /// is treated as commentary and ignored (annotated test files)
+    pub fn from_ascii_with_commentary(text: &str) -> Result<GridAscii, AsciiError> {
+        Self::parse_ascii(text, true)
+    }
+
+    fn parse_ascii(text: &str, allow_commentary: bool) -> Result<GridAscii, AsciiError> {
+        let mut cols: Option<usize> = None;
+        let mut rows = 0;
+        let mut grid_ended = false;
+        let mut blocked = Vec::new();
+        let mut observer_cells = Vec::new();
+        let mut visible = HashSet::new();
+
+        for (line_idx, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                grid_ended = rows > 0;
+                continue;
+            }
+            if grid_ended {
+                if allow_commentary {
+                    break;
+                }
+                return Err(AsciiError::ContentAfterGrid { line: line_idx + 1 });
+            }
+
+            let width = line.chars().count();
+            let expected = *cols.get_or_insert(width);
+            if width != expected {
+                return Err(AsciiError::RaggedLine { line: line_idx + 1, expected, found: width });
+            }
+
+            for (x, ch) in line.chars().enumerate() {
+                if !LEGEND.contains(&ch) {
+                    return Err(AsciiError::UnexpectedChar { line: line_idx + 1, column: x + 1, ch });
+                }
+
+                let id = (x + rows * expected) as i32;
+                match ch {
+                    OBSERVER => {
+                        observer_cells.push((x as i32, rows as i32));
+                        visible.insert(id);
+                    }
+                    BLOCKED => blocked.push(id),
+                    VISIBLE => {
+                        visible.insert(id);
+                    }
+                    _ => {} // FREE
+                }
+            }
+            rows += 1;
+        }
+
+        let cols = match cols {
+            Some(cols) => cols as i32,
+            None => return Err(AsciiError::Empty),
+        };
+        let observer = detect_observer(&observer_cells)?;
+
+        Ok(GridAscii {
+            grid: Grid::with_blocked(rows as i32, cols, &blocked),
+            observer: observer.map(|(cell, _, _)| cell),
+            messy_x: observer.is_some_and(|(_, messy_x, _)| messy_x),
+            messy_y: observer.is_some_and(|(_, _, messy_y)| messy_y),
+            visible,
+        })
+    }
+
+    /// Compact run-length form: `<rows>x<cols>:` then row-major runs like `12.3#5.`
+    /// `.` free, `#` blocked, `~` chasm; a run of length 1 has no count.
+    /// Edges and the observer are not included.
+    pub fn to_rle_string(&self) -> String {
+        let mut result = format!("{}x{}:", self.rows, self.cols);
+        let mut cells = self.cells.iter().map(|&v| rle_symbol(v)).peekable();
+
+        while let Some(symbol) = cells.next() {
+            let mut run = 1;
+            while cells.peek() == Some(&symbol) {
+                cells.next();
+                run += 1;
+            }
+            if run > 1 {
+                result.push_str(&run.to_string());
+            }
+            result.push(symbol);
+        }
+        result
+    }
+
+    /// Decode a grid produced by `to_rle_string` (whitespace is ignored)
+    /// Zero-sized grids (`0x2:`) round-trip; memory grows only with the decoded runs
+    pub fn from_rle_string(text: &str) -> Result<Grid, RleError> {
+        let (header, body) = text.trim().split_once(':').ok_or(RleError::Header)?;
+        let (rows, cols) = header.split_once('x').ok_or(RleError::Header)?;
+        let rows: i32 = rows.trim().parse().map_err(|_| RleError::Header)?;
+        let cols: i32 = cols.trim().parse().map_err(|_| RleError::Header)?;
+        if rows < 0 || cols < 0 {
+            return Err(RleError::Header);
+        }
+        let expected = rows.checked_mul(cols).ok_or(RleError::Header)? as usize;
+
+        // Grows with the decoded runs, so a huge header alone allocates nothing
+        let mut cells = Vec::new();
+        let mut count: Option<usize> = None;
+        for (offset, ch) in body.chars().enumerate() {
+            let value = match ch {
+                '0'..='9' => {
+                    let digit = ch.to_digit(10).unwrap_or(0) as usize;
+                    let run = count.unwrap_or(0).checked_mul(10).and_then(|run| run.checked_add(digit));
+                    // Anything that overflows is far longer than the grid anyway
+                    count = Some(run.unwrap_or(usize::MAX));
+                    continue;
+                }
+                c if c.is_whitespace() => continue,
+                '.' => 0,
+                '#' => 1,
+                '~' => -1,
+                _ => return Err(RleError::UnexpectedChar { offset: header.len() + 1 + offset, ch }),
+            };
+            let run = count.take().unwrap_or(1);
+            if run > expected - cells.len() {
+                return Err(RleError::LengthMismatch { expected, found: cells.len().saturating_add(run) });
+            }
+            cells.extend(std::iter::repeat_n(value, run));
+        }
+
+        if cells.len() != expected || count.is_some() {
+            return Err(RleError::LengthMismatch { expected, found: cells.len() });
+        }
+
+        let mut grid = Grid::new(rows, cols);
+        grid.cells = cells;
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_ascii_valid_map() {
+        let map = Grid::from_ascii("□□■\n□s□\n\n").unwrap();
+        assert_eq!((map.grid.rows, map.grid.cols), (2, 3));
+        assert!(map.grid.is_blocked(2, 0));
+        assert_eq!(map.observer, Some((1, 1)));
+    }
+
+    #[test]
+    fn test_from_ascii_reports_typo_position() {
+        let err = Grid::from_ascii("□□□□\n□□□x\n□s□□").err().unwrap();
+        assert_eq!(err, AsciiError::UnexpectedChar { line: 2, column: 4, ch: 'x' });
+        assert_eq!(err.to_string(), "unexpected character 'x' at line 2, column 4");
+    }
+
+    #[test]
+    fn test_from_ascii_observer_shapes() {
+        let single = Grid::from_ascii("□□□\n□s□\n□□□").unwrap();
+        assert_eq!((single.observer, single.messy_x, single.messy_y), (Some((1, 1)), false, false));
+
+        let messy_x = Grid::from_ascii("□□□\n□ss\n□□□").unwrap();
+        assert_eq!((messy_x.observer, messy_x.messy_x, messy_x.messy_y), (Some((1, 1)), true, false));
+
+        let messy_y = Grid::from_ascii("□s□\n□s□\n□□□").unwrap();
+        assert_eq!((messy_y.observer, messy_y.messy_x, messy_y.messy_y), (Some((1, 0)), false, true));
+
+        let both = Grid::from_ascii("□□□\n□ss\n□ss").unwrap();
+        assert_eq!((both.observer, both.messy_x, both.messy_y), (Some((1, 1)), true, true));
+
+        assert_eq!(Grid::from_ascii("s□s\n□□□").err(), Some(AsciiError::InvalidObserver { cells: 2 }));
+        assert_eq!(Grid::from_ascii("□□\n□□").unwrap().observer, None);
+    }
+
+    #[test]
+    fn test_from_ascii_with_commentary_ignores_trailing_text() {
+        let text = "\n□s□\n■□□\n\nNotes: o■■ is just an example\n";
+        let parsed = Grid::from_ascii_with_commentary(text).unwrap();
+        assert_eq!((parsed.grid.rows, parsed.grid.cols), (2, 3));
+        assert!(parsed.grid.is_blocked(0, 1));
+        assert_eq!(Grid::from_ascii(text).err(), Some(AsciiError::ContentAfterGrid { line: 5 }));
+    }
+
+    #[test]
+    fn test_from_ascii_rejects_rows_after_blank_line() {
+        assert_eq!(Grid::from_ascii("□s□\n\n■x■\n").err(), Some(AsciiError::ContentAfterGrid { line: 3 }));
+        assert_eq!(Grid::from_ascii("□s□\n\n■■■").err(), Some(AsciiError::ContentAfterGrid { line: 3 }));
+        assert!(Grid::from_ascii("\n\n□s□\n■■■\n\n\n").is_ok());
+    }
+
+    #[test]
+    fn test_from_ascii_rejects_ragged_rows() {
+        let err = Grid::from_ascii("□□□\n□s").err().unwrap();
+        assert_eq!(err, AsciiError::RaggedLine { line: 2, expected: 3, found: 2 });
+    }
+
+    #[test]
+    fn test_rle_round_trip_is_compact() {
+        let blocked: Vec<i32> = (0..100).map(|i| i * 97 % 10_000).chain(4_000..4_050).collect();
+        let grid = Grid::with_blocked(100, 100, &blocked);
+
+        let rle = grid.to_rle_string();
+        let decoded = Grid::from_rle_string(&rle).unwrap();
+        assert_eq!((decoded.rows, decoded.cols), (100, 100));
+        assert_eq!(decoded.cells, grid.cells);
+
+        // ASCII export is one char per cell plus newlines
+        assert!(rle.len() * 10 < 100 * 101);
+    }
+
+    #[test]
+    fn test_rle_format() {
+        let grid = Grid::with_blocked(2, 4, &[2, 3, 4]);
+        assert_eq!(grid.to_rle_string(), "2x4:2.3#3.");
+        assert_eq!(Grid::from_rle_string("2x4:2.3#2.").err(), Some(RleError::LengthMismatch { expected: 8, found: 7 }));
+        assert_eq!(Grid::from_rle_string("2x4:2.3#3?").err(), Some(RleError::UnexpectedChar { offset: 9, ch: '?' }));
+    }
+
+    #[test]
+    fn test_rle_rejects_oversized_input() {
+        assert_eq!(Grid::from_rle_string("100000x100000:.").err(), Some(RleError::Header));
+        assert_eq!(
+            Grid::from_rle_string("2x2:99999999999999999999.").err(),
+            Some(RleError::LengthMismatch { expected: 4, found: usize::MAX })
+        );
+        assert_eq!(
+            Grid::from_rle_string("2x2:4000000000.").err(),
+            Some(RleError::LengthMismatch { expected: 4, found: 4_000_000_000 })
+        );
+        assert_eq!(Grid::from_rle_string("2x2:3.2#").err(), Some(RleError::LengthMismatch { expected: 4, found: 5 }));
+        assert_eq!(
+            Grid::from_rle_string("46340x46340:.").err(),
+            Some(RleError::LengthMismatch { expected: 2_147_395_600, found: 1 })
+        );
+    }
+
+    #[test]
+    fn test_rle_round_trips_empty_grid() {
+        let mut grid = Grid::new(2, 2);
+        grid.resize(0, 2);
+        assert_eq!(grid.to_rle_string(), "0x2:");
+
+        let decoded = Grid::from_rle_string("0x2:").unwrap();
+        assert_eq!((decoded.rows, decoded.cols), (0, 2));
+        assert!(decoded.cells.is_empty());
+        assert_eq!(Grid::from_rle_string("-1x2:").err(), Some(RleError::Header));
+    }
+}
@@ -1,28 +1,14 @@
+#![allow(dead_code)]
+
 use rustgame3::{Grid, raycast};
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
-/// Test data structure matching C# JSON export format
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct RaycastTestData {
-    #[serde(rename = "testName")]
-    pub test_name: String,
-    #[serde(rename = "gridRows")]
-    pub grid_rows: i32,
-    #[serde(rename = "gridCols")]
-    pub grid_cols: i32,
-    #[serde(rename = "blockedCells")]
-    pub blocked_cells: Vec<i32>,
-    #[serde(rename = "startX")]
-    pub start_x: i32,
-    #[serde(rename = "startY")]
-    pub start_y: i32,
-    #[serde(rename = "expectedVisible")]
-    pub expected_visible: Vec<i32>,
-    pub log: Vec<String>,
-}
+pub use rustgame3::fixture::RaycastTestData;
+
+/// Parsed standard format test: (grid, start_x, start_y, expected_visible)
+pub type StandardTest = (Grid, i32, i32, HashSet<i32>);
 
 /// Load a test from JSON file
 pub fn load_test(path: &Path) -> Result<RaycastTestData, Box<dyn std::error::Error>> {
@@ -169,97 +155,17 @@ pub fn run_test(test_data: &RaycastTestData) -> (bool, Option<String>, usize, us
 }
 
 /// Parse a standard format test file
-/// Format:
-/// - s: start position (observer)
-/// - ■: blocked cell
-/// - □: free cell
-/// - o: visible free cell
-pub fn parse_standard_test(path: &Path) -> Result<(Grid, i32, i32, HashSet<i32>), Box<dyn std::error::Error>> {
+/// The format is the canonical text map (see `rustgame3::text_map`); commentary
+/// after the grid is ignored.
+pub fn parse_standard_test(path: &Path) -> Result<StandardTest, Box<dyn std::error::Error>> {
     let contents = fs::read_to_string(path)?;
-    let lines: Vec<&str> = contents.lines().collect();
-
-    // Find non-empty lines that are valid grid rows
-    let all_non_empty: Vec<&str> = lines.iter()
-        .copied()
-        .filter(|line| !line.trim().is_empty())
-        .collect();
-
-    if all_non_empty.is_empty() {
-        return Err("No non-empty lines found in test file".into());
-    }
-
-    // Determine grid width from first line
-    let grid_cols = all_non_empty[0].chars().count() as i32;
-
-    // Valid grid characters
-    let valid_chars = ['s', '■', '□', 'o', ' '];
-
-    // Collect only valid grid rows (same width and valid characters)
-    let mut grid_lines = Vec::new();
-    for line in all_non_empty {
-        let line_width = line.chars().count() as i32;
-
-        // Stop if line width doesn't match expected grid width
-        if line_width != grid_cols {
-            break;
-        }
-
-        // Stop if line contains invalid characters
-        let has_invalid = line.chars().any(|c| !valid_chars.contains(&c));
-        if has_invalid {
-            break;
-        }
-
-        grid_lines.push(line);
-    }
-
-    if grid_lines.is_empty() {
-        return Err("No valid grid lines found in test file".into());
-    }
-
-    let grid_rows = grid_lines.len() as i32;
-
-    let mut blocked_cells = Vec::new();
-    let mut start_x = -1;
-    let mut start_y = -1;
-    let mut expected_visible = HashSet::new();
-
-    for (y, line) in grid_lines.iter().enumerate() {
-        for (x, ch) in line.chars().enumerate() {
-            let cell_id = x as i32 + (y as i32) * grid_cols;
-
-            match ch {
-                's' => {
-                    start_x = x as i32;
-                    start_y = y as i32;
-                    expected_visible.insert(cell_id);
-                }
-                '■' => {
-                    blocked_cells.push(cell_id);
-                }
-                'o' => {
-                    expected_visible.insert(cell_id);
-                }
-                '□' | ' ' => {
-                    // Free cell, do nothing
-                }
-                _ => {
-                    // Unknown character, skip
-                }
-            }
-        }
-    }
-
-    if start_x == -1 || start_y == -1 {
-        return Err("No start position 's' found in test file".into());
-    }
-
-    let grid = Grid::with_blocked(grid_rows, grid_cols, &blocked_cells);
-    Ok((grid, start_x, start_y, expected_visible))
+    let parsed = Grid::from_ascii_with_commentary(&contents)?;
+    let (start_x, start_y) = parsed.observer.ok_or("No start position 's' found in test file")?;
+    Ok((parsed.grid, start_x, start_y, parsed.visible))
 }
 
 /// Flip standard test data horizontally
-pub fn flip_standard_horizontal(grid: &Grid, start_x: i32, start_y: i32, expected: &HashSet<i32>) -> (Grid, i32, i32, HashSet<i32>) {
+pub fn flip_standard_horizontal(grid: &Grid, start_x: i32, start_y: i32, expected: &HashSet<i32>) -> StandardTest {
     let mut blocked_cells = Vec::new();
     for y in 0..grid.rows {
         for x in 0..grid.cols {
@@ -284,7 +190,7 @@ pub fn flip_standard_horizontal(grid: &Grid, start_x: i32, start_y: i32, expecte
 }
 
 /// Flip standard test data vertically
-pub fn flip_standard_vertical(grid: &Grid, start_x: i32, start_y: i32, expected: &HashSet<i32>) -> (Grid, i32, i32, HashSet<i32>) {
+pub fn flip_standard_vertical(grid: &Grid, start_x: i32, start_y: i32, expected: &HashSet<i32>) -> StandardTest {
     let mut blocked_cells = Vec::new();
     for y in 0..grid.rows {
         for x in 0..grid.cols {
@@ -309,7 +215,7 @@ pub fn flip_standard_vertical(grid: &Grid, start_x: i32, start_y: i32, expected:
 }
 
 /// Flip standard test data both horizontally and vertically
-pub fn flip_standard_both(grid: &Grid, start_x: i32, start_y: i32, expected: &HashSet<i32>) -> (Grid, i32, i32, HashSet<i32>) {
+pub fn flip_standard_both(grid: &Grid, start_x: i32, start_y: i32, expected: &HashSet<i32>) -> StandardTest {
     let mut blocked_cells = Vec::new();
     for y in 0..grid.rows {
         for x in 0..grid.cols {
@@ -335,3 +241,54 @@ pub fn flip_standard_both(grid: &Grid, start_x: i32, start_y: i32, expected: &Ha
 
     (new_grid, new_start_x, new_start_y, new_expected)
 }
+
+/// Ordered pairs (a, b) of free cells where a sees b but b does not see a
+/// Only every `sample_step`-th free cell is used as a viewer (1 = all cells)
+pub fn raycast_asymmetries(grid: &Grid, sample_step: usize) -> Vec<(i32, i32)> {
+    asymmetries_and_pair_count(grid, sample_step).0
+}
+
+/// One-way pairs plus the number of visible (viewer, other cell) pairs checked
+/// Each cell is cast at most once
+fn asymmetries_and_pair_count(grid: &Grid, sample_step: usize) -> (Vec<(i32, i32)>, usize) {
+    let cast = |id: i32| {
+        let (x, y) = grid.get_coords(id);
+        raycast(grid, x, y)
+    };
+    let mut cache: HashMap<i32, HashSet<i32>> = HashMap::new();
+
+    let viewers: Vec<i32> = (0..grid.rows * grid.cols)
+        .filter(|&id| !grid.is_blocked_id(id))
+        .step_by(sample_step.max(1))
+        .collect();
+
+    let mut asymmetries = Vec::new();
+    let mut pairs = 0;
+    for a in viewers {
+        // Taken out of the cache while the other cells are looked up
+        let from_a = cache.remove(&a).unwrap_or_else(|| cast(a));
+        pairs += from_a.len().saturating_sub(1);
+        for &b in &from_a {
+            if b != a && !cache.entry(b).or_insert_with(|| cast(b)).contains(&a) {
+                asymmetries.push((a, b));
+            }
+        }
+        cache.insert(a, from_a);
+    }
+    (asymmetries, pairs)
+}
+
+/// Assert that visibility is mutual (A sees B <=> B sees A) for sampled free cells
+///
+/// The integer cone tracer is conservative around diagonal wall corners, so a few
+/// one-way pairs are expected there. `tolerance` is the allowed fraction of
+/// asymmetric pairs among all sampled visible pairs (0.0 = strictly symmetric).
+pub fn assert_raycast_symmetric(grid: &Grid, sample_step: usize, tolerance: f64) {
+    let (asymmetries, pairs) = asymmetries_and_pair_count(grid, sample_step);
+    let ratio = asymmetries.len() as f64 / pairs.max(1) as f64;
+    assert!(
+        ratio <= tolerance,
+        "{} of {} visible pairs are one-way (ratio {:.4} > {}), first: {:?}",
+        asymmetries.len(), pairs, ratio, tolerance, asymmetries.first()
+    );
+}
@@ -1,7 +1,9 @@
 mod common;
 
-use common::{load_test, run_test};
+use common::{load_test, run_single_test, run_test, RaycastTestData};
+use rustgame3::Grid;
 use std::fs;
+use std::path::Path;
 
 #[test]
 fn json_validation_tests() {
@@ -34,3 +36,18 @@ fn json_validation_tests() {
 
     println!("All {} JSON validation tests passed", passed);
 }
+
+#[test]
+fn exported_test_round_trips() {
+    let source = load_test(Path::new("./test_data/28_scattered_obstacles.json")).unwrap();
+    let grid = Grid::with_blocked(source.grid_rows, source.grid_cols, &source.blocked_cells);
+
+    let exported = RaycastTestData::capture("exported", &grid, source.start_x, source.start_y);
+    let path = std::env::temp_dir().join("rustgame3_exported_test.json");
+    fs::write(&path, serde_json::to_string_pretty(&exported).unwrap()).unwrap();
+
+    let reloaded = load_test(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!(reloaded.test_name, "exported");
+    assert!(run_single_test(&reloaded).0);
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::{assert_raycast_symmetric, load_test};
+use rustgame3::Grid;
+use std::path::Path;
+
+fn fixture_grid(name: &str) -> Grid {
+    let path = Path::new("./test_data").join(name);
+    let test_data = load_test(&path).unwrap_or_else(|e| panic!("Failed to load {}: {}", name, e));
+    Grid::with_blocked(test_data.grid_rows, test_data.grid_cols, &test_data.blocked_cells)
+}
+
+#[test]
+fn open_and_straight_wall_grids_are_symmetric() {
+    for name in ["01_empty_grid_center.json", "06_wall_below.json", "31_ring_obstacles.json"] {
+        assert_raycast_symmetric(&fixture_grid(name), 1, 0.0);
+    }
+}
+
+#[test]
+fn diagonal_grids_are_nearly_symmetric() {
+    // Diagonal walls produce a handful of one-way pairs from conservative rounding
+    for name in ["15_diagonal_wall.json", "72_diagonal_from_left.json", "88_scattered_varying_distances.json"] {
+        assert_raycast_symmetric(&fixture_grid(name), 1, 0.02);
+    }
+}